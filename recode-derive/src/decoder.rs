@@ -1,15 +1,30 @@
-use darling::util::Flag;
+use darling::{
+    ast::{Data, Fields},
+    util::Flag,
+};
 use proc_macro2::TokenStream;
+use syn::ext::IdentExt;
 
 use crate::util::*;
 
 #[derive(Debug, darling::FromDeriveInput)]
-#[darling(forward_attrs(allow, doc, cfg))]
-#[darling(attributes(recode), supports(struct_named, struct_unit))]
+#[darling(forward_attrs(allow, doc, cfg, repr))]
+#[darling(attributes(recode), supports(struct_any, enum_any))]
 pub(crate) struct Decoder {
     pub(crate) ident: syn::Ident,
     pub(crate) generics: syn::Generics,
-    pub(crate) data: darling::ast::Data<(), DecoderField>,
+    pub(crate) attrs: Vec<syn::Attribute>,
+    pub(crate) data: Data<DecoderVariant, DecoderField>,
+    pub(crate) tag: Option<syn::Type>,
+    /// The integer type that the ids of a C-like enum are encoded as, instead
+    /// of the one of its `#[repr(...)]` attribute.
+    pub(crate) repr: Option<syn::Type>,
+    pub(crate) transparent: Flag,
+    pub(crate) endian: Option<Endian>,
+    pub(crate) ctx: Option<syn::Type>,
+    pub(crate) magic: Option<syn::LitByteStr>,
+    pub(crate) bits: Option<u32>,
+    pub(crate) bit_order: Option<BitOrder>,
     #[darling(default)]
     pub(crate) decoder: DecoderOpts,
 }
@@ -23,11 +38,37 @@ pub(crate) struct DecoderOpts {
     pub(crate) buffer_name: Option<syn::Ident>,
 }
 
-#[derive(Debug, darling::FromField)]
+#[derive(Clone, Debug, darling::FromVariant)]
+#[darling(attributes(recode))]
+pub(crate) struct DecoderVariant {
+    pub(crate) ident: syn::Ident,
+    pub(crate) fields: Fields<DecoderField>,
+    /// The id of the variant, which defaults to its discriminant (see
+    /// `resolve_ids`).
+    pub(crate) id: Option<syn::Expr>,
+    pub(crate) discriminant: Option<syn::Expr>,
+    /// Whether the variant stands for every id that matches no other variant,
+    /// which it keeps in its single field.
+    pub(crate) other: Flag,
+}
+
+#[derive(Clone, Debug, darling::FromField)]
 #[darling(attributes(recode))]
 pub(crate) struct DecoderField {
     pub(crate) ident: Option<syn::Ident>,
     pub(crate) ty: syn::Type,
+    /// Bytes without a struct member that precede the field.
+    #[darling(default)]
+    pub(crate) before: Filler,
+    /// Bytes without a struct member that follow the field.
+    #[darling(default)]
+    pub(crate) after: Filler,
+    pub(crate) bits: Option<u32>,
+    /// A type that the field is decoded as, and converted from with `From`.
+    pub(crate) via: Option<syn::Type>,
+    /// A type that the field is decoded as, and converted from with
+    /// `TryFrom`.
+    pub(crate) try_from: Option<syn::Type>,
     #[darling(default)]
     pub(crate) decoder: DecoderFieldOpts,
 }
@@ -37,19 +78,37 @@ pub(crate) struct DecoderField {
 pub(crate) struct DecoderFieldOpts {
     pub(crate) skip: Flag,
     pub(crate) skip_if: Option<syn::Expr>,
+    /// A function that computes the value of the field from the decoded
+    /// value.
     pub(crate) map: Option<syn::Expr>,
     pub(crate) with: Option<syn::Type>,
     pub(crate) validate: Option<syn::Expr>,
+    pub(crate) len: Option<syn::Expr>,
+    /// The number of elements of the field, each of which is decoded with
+    /// `with` (or the element type of the field, by default).
+    pub(crate) count: Option<syn::Expr>,
 }
 
 impl darling::ToTokens for Decoder {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         use quote::quote;
 
+        let data = &self.data();
+        let tag = &self.tag();
+        let item = &self.tag_item();
         let Decoder {
             ident,
             generics,
-            data,
+            attrs: _,
+            data: _,
+            tag: _,
+            repr: _,
+            transparent,
+            endian: _,
+            ctx,
+            magic,
+            bits,
+            bit_order,
             decoder:
                 DecoderOpts {
                     disable,
@@ -63,98 +122,876 @@ impl darling::ToTokens for Decoder {
             return;
         }
 
+        if let (Data::Enum(variants), Some(_)) = (data, bits) {
+            tokens.extend(self.to_from_bits(variants));
+            return;
+        }
+
+        if let (Some(magic), true) = (magic, transparent.is_present()) {
+            tokens.extend(
+                darling::Error::custom(
+                    "transparent structs cannot have magic bytes",
+                )
+                .with_span(magic)
+                .write_errors(),
+            );
+            return;
+        }
+
         let output_type = output_type
             .clone()
             .unwrap_or(syn::Type::Verbatim(quote!(Self)));
-        let error = error.clone().unwrap_or(box_type());
         let buffer_name = buffer_name.clone().unwrap_or(default_buffer_name());
+        let mut error = error.clone().unwrap_or(box_type());
+        let mut has_enough_bytes = TokenStream::new();
+        let mut bounds;
+        let packing = Packing {
+            bits: *bits,
+            order: bit_order.unwrap_or_default(),
+            endian: self.endian,
+        };
 
-        let fields: Vec<_> = data
-            .as_ref()
-            .take_struct()
-            .expect("only structs are supported")
-            .fields;
+        let body = match data {
+            | Data::Struct(fields) => {
+                if transparent.is_present() {
+                    let [field] = fields.fields.as_slice() else {
+                        tokens.extend(
+                            darling::Error::custom(
+                                "transparent structs must have exactly one \
+                                 field",
+                            )
+                            .with_span(ident)
+                            .write_errors(),
+                        );
+                        return;
+                    };
 
-        let field_names = fields.iter().map(|f| f.ident());
-        let field_exprs =
-            fields.iter().map(|&f| f.to_decode_stmt(&buffer_name));
+                    let ty = field.wire_type();
+                    let with = field.decoder.with.as_ref().unwrap_or(ty);
+
+                    if self.decoder.error.is_none() {
+                        error = match ctx {
+                            | Some(ctx) => syn::parse_quote! {
+                                <#with as recode::DecodeWith<#ctx, #ty>>::Error
+                            },
+                            | None => syn::parse_quote! {
+                                <#with as recode::Decoder<#ty>>::Error
+                            },
+                        };
+                    }
+
+                    bounds = field.size_bounds();
+                    has_enough_bytes = quote! {
+                        #[inline]
+                        fn has_enough_bytes<__B: recode::bytes::Buf>(
+                            buf: &__B,
+                        ) -> bool {
+                            <#with as recode::Decoder<#ty>>::has_enough_bytes(
+                                buf,
+                            )
+                        }
+                    };
+                } else {
+                    bounds = to_size_bounds(fields, packing);
+                }
+
+                let context = (!transparent.is_present())
+                    .then(|| ident.unraw().to_string());
+                let stmts = to_decode_stmts(
+                    fields,
+                    &buffer_name,
+                    context.as_deref(),
+                    ctx.as_ref(),
+                    packing,
+                );
+                let constructor = to_constructor(fields, quote!(#output_type));
+
+                quote! {
+                    #stmts
+
+                    Ok(#constructor)
+                }
+            }
+            | Data::Enum(variants) => {
+                let Some(tag) = tag else {
+                    tokens.extend(
+                        darling::Error::missing_field("tag")
+                            .with_span(ident)
+                            .write_errors(),
+                    );
+                    return;
+                };
+
+                let others: Vec<_> = variants
+                    .iter()
+                    .filter(|v| v.other.is_present())
+                    .map(|v| (&v.ident, v.fields.len()))
+                    .collect();
+
+                if let Err(e) = check_other(&others) {
+                    tokens.extend(e.write_errors());
+                    return;
+                }
+
+                bounds = SizeBounds::sum([
+                    SizeBounds::of(&quote!(recode::Decoder), tag, item),
+                    SizeBounds::any(variants.iter().map(|v| {
+                        match v.other.is_present() {
+                            | true => SizeBounds::exact(0),
+                            | false => to_size_bounds(&v.fields, packing),
+                        }
+                    })),
+                ]);
+
+                let name = ident.unraw().to_string();
+                let arms = variants
+                    .iter()
+                    .filter(|v| !v.other.is_present())
+                    .map(|v| {
+                        v.to_decode_arm(
+                            &output_type,
+                            item,
+                            &buffer_name,
+                            &name,
+                            ctx.as_ref(),
+                            packing,
+                        )
+                    });
+                let fallback =
+                    match variants.iter().find(|v| v.other.is_present()) {
+                        | Some(v) => {
+                            let value =
+                                v.to_other_value(&output_type, item, &name);
+
+                            quote!(Ok(#value))
+                        }
+                        | None => {
+                            let unknown = unknown_tag(item);
+
+                            quote! {
+                                Err(recode::error::add_context::<Self::Error>(
+                                    #unknown.into(),
+                                    #name,
+                                    None,
+                                    0,
+                                ))
+                            }
+                        }
+                    };
+
+                quote! {
+                    let __tag = <#tag as recode::Decoder<#item>>::decode(
+                        #buffer_name,
+                    )
+                    .map_err(|e| {
+                        recode::error::add_context::<Self::Error>(
+                            e.into(),
+                            #name,
+                            None,
+                            0,
+                        )
+                    })?;
+
+                    #( #arms )*
+
+                    #fallback
+                }
+            }
+        };
 
         let (imp, ty, wher) = generics.split_for_impl();
 
-        tokens.extend(quote::quote! {
-            impl #imp recode::Decoder<#output_type> for #ident #ty #wher {
-                type Error = #error;
+        if let Some(magic) = magic {
+            bounds = SizeBounds::sum([
+                SizeBounds::exact(magic.value().len()),
+                bounds,
+            ]);
+        }
+
+        let magic = magic.as_ref().map(|magic| {
+            let name = ident.unraw().to_string();
 
-                fn decode(
-                    #buffer_name: &mut recode::bytes::BytesMut,
-                ) -> Result<#output_type, Self::Error>
+            quote! {
+                recode::util::BufExt::expect_magic(#buffer_name, #magic)
+                    .map_err(|e| {
+                        recode::error::add_context::<Self::Error>(
+                            e.into(),
+                            #name,
+                            None,
+                            0,
+                        )
+                    })?;
+            }
+        });
+        let body = quote! {
+            use recode::{bytes::Buf as _, Decoder};
+
+            let __len = #buffer_name.remaining();
+
+            #magic
+
+            #body
+        };
+
+        let consts = bounds.to_consts();
+
+        // with a context type, the value can only be decoded through
+        // `DecodeWith`, which is what the fields of the container are
+        // decoded with as well
+        tokens.extend(match ctx {
+            | Some(ctx) => quote! {
+                impl #imp recode::DecodeWith<#ctx, #output_type>
+                    for #ident #ty #wher
                 {
-                    use recode::Decoder;
+                    type Error = #error;
 
-                    #( #field_exprs )*
+                    #[allow(unused_variables)]
+                    fn decode_with<__B: recode::bytes::Buf>(
+                        #buffer_name: &mut __B,
+                        __ctx: &#ctx,
+                    ) -> Result<#output_type, Self::Error>
+                    {
+                        let ctx = __ctx;
 
-                    Ok(#output_type {
-                        #(#field_names), *
-                    })
+                        #body
+                    }
                 }
-            }
+            },
+            | None => quote! {
+                impl #imp recode::Decoder<#output_type> for #ident #ty #wher {
+                    type Error = #error;
+
+                    #consts
+
+                    #[allow(unused_variables)]
+                    fn decode<__B: recode::bytes::Buf>(
+                        #buffer_name: &mut __B,
+                    ) -> Result<#output_type, Self::Error>
+                    {
+                        #body
+                    }
+
+                    #has_enough_bytes
+                }
+            },
         });
     }
 }
 
+impl Decoder {
+    /// Returns the tag of the input, which defaults to the `repr` type of
+    /// C-like enums.
+    pub(crate) fn tag(&self) -> Option<syn::Type> {
+        self.tag
+            .clone()
+            .or_else(|| repr_tag(self.repr.as_ref(), &self.attrs, self.endian))
+    }
+
+    /// Returns the type the tag of the input is decoded into.
+    pub(crate) fn tag_item(&self) -> syn::Type {
+        tag_item(self.tag.as_ref(), self.repr.as_ref(), &self.attrs)
+    }
+
+    /// Returns the fields of the input, where numeric fields without an
+    /// explicit codec use the container's byte order, and the ids of its
+    /// variants.
+    pub(crate) fn data(&self) -> Data<DecoderVariant, DecoderField> {
+        let mut data = self.data.clone();
+
+        if let Data::Enum(ref mut variants) = data {
+            resolve_ids(
+                variants
+                    .iter_mut()
+                    .map(|v| (&mut v.id, v.discriminant.as_ref())),
+            );
+        }
+
+        let Some(endian) = self.endian else {
+            return data;
+        };
+
+        let fields: Vec<&mut DecoderField> = match data {
+            | Data::Struct(ref mut fields) => {
+                fields.fields.iter_mut().collect()
+            }
+            | Data::Enum(ref mut variants) => variants
+                .iter_mut()
+                .flat_map(|v| v.fields.fields.iter_mut())
+                .collect(),
+        };
+
+        for field in fields {
+            if field.decoder.with.is_none() {
+                field.decoder.with = endian.codec_for(field.wire_type());
+            }
+
+            field.before.set_endian(endian);
+            field.after.set_endian(endian);
+        }
+
+        data
+    }
+
+    /// Generates a `FromBits` implementation for a `bits` enum, which
+    /// converts the ids of its unit variants.
+    fn to_from_bits(&self, variants: &[DecoderVariant]) -> TokenStream {
+        use quote::quote;
+
+        if let Some(v) = variants.iter().find(|v| !v.fields.is_unit()) {
+            return darling::Error::custom(
+                "variants of `bits` enums cannot have fields",
+            )
+            .with_span(&v.ident)
+            .write_errors();
+        }
+
+        let ident = &self.ident;
+        let (imp, ty, wher) = self.generics.split_for_impl();
+        let arms =
+            variants.iter().map(|DecoderVariant { ident, id, .. }| {
+                quote! {
+                    if bits == (#id) as u64 {
+                        return Ok(Self::#ident);
+                    }
+                }
+            });
+
+        quote! {
+            impl #imp recode::bits::FromBits for #ident #ty #wher {
+                fn from_bits(bits: u64) -> Result<Self, recode::Error> {
+                    #( #arms )*
+
+                    Err(recode::Error::UnknownTag(bits as usize))
+                }
+            }
+        }
+    }
+}
+
+impl DecoderVariant {
+    fn to_decode_arm(
+        &self,
+        output_type: &syn::Type,
+        tag_item: &syn::Type,
+        buf_ident: &syn::Ident,
+        enum_name: &str,
+        ctx: Option<&syn::Type>,
+        packing: Packing,
+    ) -> TokenStream {
+        use quote::quote;
+
+        let DecoderVariant {
+            ident, fields, id, ..
+        } = self;
+
+        let context = format!("{}::{}", enum_name, ident.unraw());
+        let stmts =
+            to_decode_stmts(fields, buf_ident, Some(&context), ctx, packing);
+        let constructor = to_constructor(fields, quote!(#output_type::#ident));
+
+        quote! {
+            if __tag == (#id) as #tag_item {
+                #stmts
+
+                return Ok(#constructor);
+            }
+        }
+    }
+
+    /// Generates an expression that constructs the `other` variant from the
+    /// unknown id in `__tag`, of type `tag_item`.
+    pub(crate) fn to_other_value(
+        &self,
+        output_type: &syn::Type,
+        tag_item: &syn::Type,
+        enum_name: &str,
+    ) -> TokenStream {
+        use quote::quote;
+
+        let ident = &self.ident;
+        let field = &self.fields.fields[0];
+        let (member, binding) = field_binding(field.ident.as_ref(), 0);
+        let name = field_name(field.ident.as_ref(), 0);
+        let context = format!("{}::{}", enum_name, ident.unraw());
+        let convert = Conversion::TryFrom(tag_item).to_field(
+            &field.ty,
+            &quote!(__tag),
+            &name,
+        );
+
+        quote! {
+            {
+                let #binding = #convert.map_err(|e| {
+                    recode::error::add_context::<Self::Error>(
+                        e.into(),
+                        #context,
+                        Some(#name),
+                        0,
+                    )
+                })?;
+
+                #output_type::#ident { #member: #binding }
+            }
+        }
+    }
+}
+
 impl DecoderField {
-    fn ident(&self) -> &syn::Ident {
-        self.ident
-            .as_ref()
-            .expect("only named fields are currently supported")
+    /// Returns how the field is converted from the type it is decoded as.
+    pub(crate) fn conversion(&self) -> darling::Result<Option<Conversion<'_>>> {
+        Conversion::new(self.via.as_ref(), self.try_from.as_ref())
+    }
+
+    /// Returns the type the field is decoded as.
+    pub(crate) fn wire_type(&self) -> &syn::Type {
+        match self.conversion() {
+            | Ok(Some(conversion)) => conversion.wire_type(),
+            | _ => &self.ty,
+        }
     }
 
-    fn to_decode_stmt(&self, buf_ident: &syn::Ident) -> TokenStream {
+    /// Generates the statement that decodes the field `name` into `binding`.
+    ///
+    /// If `ty_name` (the name of the type) is given, errors are annotated with
+    /// the location of the field. If `ctx` (the context type of the container)
+    /// is given, the field is decoded with it.
+    fn to_decode_stmt(
+        &self,
+        binding: &syn::Ident,
+        buf_ident: &syn::Ident,
+        ty_name: Option<&str>,
+        name: &str,
+        ctx: Option<&syn::Type>,
+    ) -> TokenStream {
         use quote::quote;
 
         let DecoderField {
-            ident,
+            ident: _,
             ty,
+            before: _,
+            after: _,
+            bits: _,
+            via: _,
+            try_from: _,
             decoder:
                 DecoderFieldOpts {
                     skip,
                     skip_if,
                     map,
                     with,
-                    validate,
+                    validate: _,
+                    len,
+                    count,
                 },
         } = self;
 
         if skip.is_present() {
-            return quote::quote! ( let #ident = Default::default(); );
+            return quote::quote! ( let #binding = Default::default(); );
         }
 
-        let with = with.as_ref().unwrap_or(ty);
+        let conversion = match self.conversion() {
+            | Ok(conversion) => conversion,
+            | Err(e) => return e.write_errors(),
+        };
+        let wire = self.wire_type();
+        let context = ty_name.map(|ty| (ty, name));
+        let (offset, map_err) = context_tokens(buf_ident, context);
+
+        let decode = match (len, count) {
+            | (Some(_), Some(_)) => {
+                return darling::Error::custom(
+                    "`len` and `count` cannot be used together",
+                )
+                .with_span(ty)
+                .write_errors();
+            }
+            | (Some(len), None) => {
+                let frame = quote::format_ident!("__frame");
+                let with = with.as_ref().unwrap_or(wire);
+                let decode = decode_call(with, wire, &frame, ctx);
+
+                quote! {
+                    recode::util::BufExt::decode_exact(
+                        #buf_ident,
+                        #len,
+                        |#frame| #decode,
+                    )
+                }
+            }
+            | (None, Some(_)) if conversion.is_some() => {
+                return darling::Error::custom(
+                    "`count` cannot be used with `via` or `try_from`",
+                )
+                .with_span(ty)
+                .write_errors();
+            }
+            | (None, Some(count)) => {
+                let (with, item) = match with {
+                    | Some(with) => (with.clone(), syn::parse_quote!(_)),
+                    | None => match element_type(ty) {
+                        | Some(item) => (item.clone(), item),
+                        | None => {
+                            return darling::Error::custom(
+                                "`count` requires `with` for fields that are \
+                                 not generic collections",
+                            )
+                            .with_span(ty)
+                            .write_errors();
+                        }
+                    },
+                };
+                let decode = decode_call(&with, &item, buf_ident, ctx);
+
+                quote! {
+                    (0..(#count))
+                        .map(|_| #decode)
+                        .collect::<Result<#ty, _>>()
+                }
+            }
+            | (None, None) => {
+                decode_call(with.as_ref().unwrap_or(wire), wire, buf_ident, ctx)
+            }
+        };
         let map = map
             .as_ref()
             .map(|m| quote!(.map(#m)))
             .unwrap_or(TokenStream::new());
-        let validate = validate
-            .as_ref()
-            .map(|v| quote!((#v)(&#ident, #buf_ident)?;))
-            .unwrap_or(TokenStream::new());
+        let decode = match conversion {
+            | Some(conversion) => {
+                let convert = conversion.to_field(ty, &quote!(#binding), name);
+
+                quote! {
+                    let #binding = #decode #map_err ?;
+                    let #binding = #convert #map #map_err ?;
+                }
+            }
+            | None => quote!(let #binding = #decode #map #map_err ?;),
+        };
+        let validate = self.to_validate_stmt(binding, buf_ident, &map_err);
 
         if let Some(ref skip_if) = skip_if {
             quote::quote! {
-                let #ident = if (#skip_if) {
+                #offset
+
+                let #binding = if (#skip_if) {
                     Default::default()
                 } else {
-                    <#with as recode::Decoder<#ty>>::decode(#buf_ident) #map ?
+                    #decode
+
+                    #binding
                 };
 
                 #validate
             }
         } else {
             quote::quote! {
-                let #ident = <#with as recode::Decoder<#ty>>::decode(#buf_ident) #map ?;
+                #offset
+
+                #decode
 
                 #validate
             }
         }
     }
+
+    /// Generates the statement that checks the decoded value of the field in
+    /// `binding` with its validator.
+    fn to_validate_stmt(
+        &self,
+        binding: &syn::Ident,
+        buf_ident: &syn::Ident,
+        map_err: &TokenStream,
+    ) -> TokenStream {
+        use quote::quote;
+
+        self.decoder
+            .validate
+            .as_ref()
+            .map(|v| quote!((#v)(&#binding, #buf_ident) #map_err ?;))
+            .unwrap_or_default()
+    }
+
+    /// Returns the bounds of the number of bytes the field is decoded from.
+    fn size_bounds(&self) -> SizeBounds {
+        let DecoderFieldOpts {
+            skip,
+            skip_if,
+            with,
+            len,
+            count,
+            ..
+        } = &self.decoder;
+
+        if skip.is_present() {
+            return SizeBounds::exact(0);
+        }
+
+        if len.is_some() || count.is_some() {
+            return SizeBounds::unbounded();
+        }
+
+        let ty = self.wire_type();
+        let bounds = SizeBounds::of(
+            &quote::quote!(recode::Decoder),
+            with.as_ref().unwrap_or(ty),
+            ty,
+        );
+
+        match skip_if {
+            | Some(_) => bounds.optional(),
+            | None => bounds,
+        }
+    }
+
+    /// Returns how the field is laid out.
+    fn layout(&self) -> FieldLayout {
+        match self.bits {
+            | _ if self.decoder.skip.is_present() => FieldLayout::Skipped,
+            | Some(bits) => FieldLayout::Bits(bits),
+            | None => FieldLayout::Bytes,
+        }
+    }
+}
+
+/// Generates the statements that decode a run of bit fields from a backing
+/// integer that is `width` bits wide.
+fn to_bits_stmts(
+    fields: &Fields<DecoderField>,
+    run: &[(usize, u32)],
+    width: u32,
+    buf_ident: &syn::Ident,
+    ty_name: Option<&str>,
+    packing: Packing,
+) -> TokenStream {
+    use quote::quote;
+
+    let (backing, codec) = packing.backing(width);
+    let order = packing.order;
+    let context = |i: usize| {
+        let name = field_name(fields.fields[i].ident.as_ref(), i);
+
+        context_tokens(buf_ident, ty_name.map(|ty| (ty, name.as_str())))
+    };
+    let (offset, map_err) = context(run[0].0);
+    let reads = run.iter().map(|&(i, bits)| {
+        let field = &fields.fields[i];
+        let (_, binding) = field_binding(field.ident.as_ref(), i);
+        let (_, map_err) = context(i);
+        let validate = field.to_validate_stmt(&binding, buf_ident, &map_err);
+        let read = match field.conversion() {
+            | Ok(Some(conversion)) => {
+                let wire = conversion.wire_type();
+                let name = field_name(field.ident.as_ref(), i);
+                let convert =
+                    conversion.to_field(&field.ty, &quote!(#binding), &name);
+
+                quote! {
+                    let #binding = __bits.read::<#wire>(#bits) #map_err ?;
+                    let #binding = #convert #map_err ?;
+                }
+            }
+            | Ok(None) => {
+                let ty = &field.ty;
+
+                quote!(let #binding = __bits.read::<#ty>(#bits) #map_err ?;)
+            }
+            | Err(e) => e.write_errors(),
+        };
+
+        quote! {
+            #read
+
+            #validate
+        }
+    });
+
+    quote! {
+        #offset
+
+        let mut __bits = recode::bits::BitReader::new(
+            <#codec as recode::Decoder<#backing>>::decode(#buf_ident)
+                #map_err ?,
+            #width,
+            #order,
+        );
+
+        #( #reads )*
+    }
+}
+
+/// Generates the statements that decode `fields` into local bindings.
+///
+/// If `ty_name` is given, errors are annotated with the name of the type and
+/// the failing field.
+fn to_decode_stmts(
+    fields: &Fields<DecoderField>,
+    buf_ident: &syn::Ident,
+    ty_name: Option<&str>,
+    ctx: Option<&syn::Type>,
+    packing: Packing,
+) -> TokenStream {
+    let layouts: Vec<_> = fields.iter().map(DecoderField::layout).collect();
+    let runs = match to_runs(&layouts, packing) {
+        | Ok(runs) => runs,
+        | Err(e) => return darling::Error::custom(e).write_errors(),
+    };
+
+    runs.iter()
+        .map(|run| match *run {
+            | Run::Field(i) => {
+                let f = &fields.fields[i];
+                let (_, binding) = field_binding(f.ident.as_ref(), i);
+                let name = field_name(f.ident.as_ref(), i);
+                let context = ty_name.map(|ty| (ty, name.as_str()));
+                let before = to_filler_stmts(&f.before, buf_ident, context);
+                let after = to_filler_stmts(&f.after, buf_ident, context);
+                let decode =
+                    f.to_decode_stmt(&binding, buf_ident, ty_name, &name, ctx);
+
+                quote::quote! {
+                    #before
+                    #decode
+                    #after
+                }
+            }
+            | Run::Bits {
+                fields: ref run,
+                width,
+            } => {
+                let fillers = run.iter().flat_map(|&(i, _)| {
+                    [&fields.fields[i].before, &fields.fields[i].after]
+                });
+
+                match check_bits_fillers(fillers) {
+                    | Ok(()) => to_bits_stmts(
+                        fields, run, width, buf_ident, ty_name, packing,
+                    ),
+                    | Err(e) => e.write_errors(),
+                }
+            }
+        })
+        .collect()
+}
+
+/// Generates the statements that decode and check `filler`, with errors
+/// annotated with `context` (the name of the type, and of the field).
+fn to_filler_stmts(
+    filler: &Filler,
+    buf_ident: &syn::Ident,
+    context: Option<(&str, &str)>,
+) -> TokenStream {
+    use quote::quote;
+
+    let (offset, map_err) = context_tokens(buf_ident, context);
+
+    filler
+        .0
+        .iter()
+        .map(|item| {
+            let check = match item {
+                | FillerItem::Magic(magic) => quote! {
+                    recode::util::BufExt::expect_magic(#buf_ident, #magic)
+                        #map_err ?;
+                },
+                | FillerItem::Constant(constant) => {
+                    let FillerConstant { ty, value, with } = &**constant;
+
+                    let with = with.as_ref().unwrap_or(ty);
+                    let decode = decode_call(with, ty, buf_ident, None);
+
+                    quote! {
+                        let __constant: #ty = #decode #map_err ?;
+                        let __expected: #ty = #value;
+
+                        if __constant != __expected {
+                            Err::<(), recode::Error>(
+                                recode::Error::invalid_constant(
+                                    &__expected,
+                                    &__constant,
+                                ),
+                            )
+                            #map_err ?;
+                        }
+                    }
+                }
+                | FillerItem::Reserved(bytes) => quote! {
+                    recode::util::BufExt::skip_reserved(#buf_ident, #bytes)
+                        #map_err ?;
+                },
+            };
+
+            quote! {
+                #offset
+                #check
+            }
+        })
+        .collect()
+}
+
+/// Generates the statement that records the offset of a field in `__offset`,
+/// along with the `map_err` call that annotates errors with `context` (the
+/// name of the type, and of the field).
+pub(crate) fn context_tokens(
+    buf_ident: &syn::Ident,
+    context: Option<(&str, &str)>,
+) -> (TokenStream, TokenStream) {
+    use quote::quote;
+
+    match context {
+        | Some((ty_name, field_name)) => (
+            quote!(let __offset = __len - #buf_ident.remaining();),
+            quote! {
+                .map_err(|e| {
+                    recode::error::add_context::<Self::Error>(
+                        e.into(),
+                        #ty_name,
+                        Some(#field_name),
+                        __offset,
+                    )
+                })
+            },
+        ),
+        | None => (TokenStream::new(), TokenStream::new()),
+    }
+}
+
+/// Returns the bounds of the number of bytes `fields` are decoded from.
+fn to_size_bounds(
+    fields: &Fields<DecoderField>,
+    packing: Packing,
+) -> SizeBounds {
+    let layouts: Vec<_> = fields.iter().map(DecoderField::layout).collect();
+
+    // invalid layouts are reported by `to_decode_stmts`
+    let runs = to_runs(&layouts, packing).unwrap_or_default();
+
+    SizeBounds::of_runs(&runs, |i| {
+        let field = &fields.fields[i];
+        let codec = quote::quote!(recode::Decoder);
+
+        SizeBounds::sum([
+            field.before.size_bounds(&codec),
+            field.size_bounds(),
+            field.after.size_bounds(&codec),
+        ])
+    })
+}
+
+/// Generates an expression that constructs `path` from the local bindings of
+/// `fields`.
+pub(crate) fn to_constructor(
+    fields: &Fields<DecoderField>,
+    path: TokenStream,
+) -> TokenStream {
+    let inits = fields.iter().enumerate().map(|(i, f)| {
+        let (member, binding) = field_binding(f.ident.as_ref(), i);
+
+        quote::quote!(#member: #binding)
+    });
+
+    quote::quote! {
+        #path {
+            #( #inits ), *
+        }
+    }
 }
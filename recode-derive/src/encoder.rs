@@ -1,15 +1,30 @@
-use darling::{util::Flag, ToTokens};
+use darling::{
+    ast::{Data, Fields},
+    util::Flag,
+    ToTokens,
+};
 use proc_macro2::TokenStream;
 
 use crate::util::*;
 
 #[derive(Debug, darling::FromDeriveInput)]
-#[darling(forward_attrs(allow, doc, cfg))]
-#[darling(attributes(recode), supports(struct_named, struct_unit))]
+#[darling(forward_attrs(allow, doc, cfg, repr))]
+#[darling(attributes(recode), supports(struct_any, enum_any))]
 pub(crate) struct Encoder {
     pub(crate) ident: syn::Ident,
     pub(crate) generics: syn::Generics,
-    pub(crate) data: darling::ast::Data<(), EncoderField>,
+    pub(crate) attrs: Vec<syn::Attribute>,
+    pub(crate) data: Data<EncoderVariant, EncoderField>,
+    pub(crate) tag: Option<syn::Type>,
+    /// The integer type that the ids of a C-like enum are encoded as, instead
+    /// of the one of its `#[repr(...)]` attribute.
+    pub(crate) repr: Option<syn::Type>,
+    pub(crate) transparent: Flag,
+    pub(crate) endian: Option<Endian>,
+    pub(crate) ctx: Option<syn::Type>,
+    pub(crate) magic: Option<syn::LitByteStr>,
+    pub(crate) bits: Option<u32>,
+    pub(crate) bit_order: Option<BitOrder>,
     #[darling(default)]
     pub(crate) encoder: EncoderOpts,
 }
@@ -24,11 +39,37 @@ pub(crate) struct EncoderOpts {
     pub(crate) input_name: Option<syn::Ident>,
 }
 
-#[derive(Debug, darling::FromField)]
+#[derive(Clone, Debug, darling::FromVariant)]
+#[darling(attributes(recode))]
+pub(crate) struct EncoderVariant {
+    pub(crate) ident: syn::Ident,
+    pub(crate) fields: Fields<EncoderField>,
+    /// The id of the variant, which defaults to its discriminant (see
+    /// `resolve_ids`).
+    pub(crate) id: Option<syn::Expr>,
+    pub(crate) discriminant: Option<syn::Expr>,
+    /// Whether the variant stands for every id that matches no other variant,
+    /// which it keeps in its single field.
+    pub(crate) other: Flag,
+}
+
+#[derive(Clone, Debug, darling::FromField)]
 #[darling(attributes(recode))]
 pub(crate) struct EncoderField {
     pub(crate) ident: Option<syn::Ident>,
     pub(crate) ty: syn::Type,
+    /// Bytes without a struct member that precede the field.
+    #[darling(default)]
+    pub(crate) before: Filler,
+    /// Bytes without a struct member that follow the field.
+    #[darling(default)]
+    pub(crate) after: Filler,
+    pub(crate) bits: Option<u32>,
+    /// A type that the field is converted into with `From`, and encoded as.
+    pub(crate) via: Option<syn::Type>,
+    /// A type that the field is converted into with `TryFrom`, and encoded
+    /// as.
+    pub(crate) try_from: Option<syn::Type>,
     #[darling(default)]
     pub(crate) encoder: EncoderFieldOpts,
 }
@@ -38,20 +79,42 @@ pub(crate) struct EncoderField {
 pub(crate) struct EncoderFieldOpts {
     pub(crate) skip: Flag,
     pub(crate) skip_if: Option<syn::Expr>,
+    /// A function that returns a reference to the encoded value, given a
+    /// reference to the field.
     pub(crate) map: Option<syn::Expr>,
+    /// A function that computes the encoded value from a reference to the
+    /// field (the inverse of the decoder's `map`). This is set by the shared
+    /// `map(encode = ..)` of `Recode`, and `map` takes precedence over it.
+    #[darling(skip)]
+    pub(crate) map_encode: Option<syn::Expr>,
     pub(crate) with: Option<syn::Type>,
     pub(crate) size: Option<syn::Expr>,
     pub(crate) validate: Option<syn::Expr>,
+    /// An expression that computes the encoded value of the field from the
+    /// other fields, instead of using the stored value.
+    pub(crate) value: Option<syn::Expr>,
 }
 
 impl darling::ToTokens for Encoder {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         use quote::quote;
 
+        let data = &self.data();
+        let tag = &self.tag();
+        let item = &self.tag_item();
         let Encoder {
             ident,
             generics,
-            data,
+            attrs: _,
+            data: _,
+            tag: _,
+            repr: _,
+            transparent,
+            endian: _,
+            ctx,
+            magic,
+            bits,
+            bit_order,
             encoder:
                 EncoderOpts {
                     disable,
@@ -66,73 +129,492 @@ impl darling::ToTokens for Encoder {
             return;
         }
 
+        if let (Data::Enum(variants), Some(_)) = (data, bits) {
+            tokens.extend(self.to_to_bits(variants));
+            return;
+        }
+
+        if let (Some(magic), true) = (magic, transparent.is_present()) {
+            tokens.extend(
+                darling::Error::custom(
+                    "transparent structs cannot have magic bytes",
+                )
+                .with_span(magic)
+                .write_errors(),
+            );
+            return;
+        }
+
         let input_type = input_type
             .clone()
             .unwrap_or(syn::Type::Verbatim(quote!(Self)));
         let input_name =
             input_name.clone().unwrap_or(quote::format_ident!("input"));
 
-        let error = error.clone().unwrap_or(box_type());
         let buf_name = buffer_name.clone().unwrap_or(default_buffer_name());
+        let mut error = error.clone().unwrap_or(box_type());
 
-        let fields: Vec<_> = data
-            .as_ref()
-            .take_struct()
-            .expect("only structs are supported")
-            .fields;
+        // transparent encoders leave the check to the field's encoder, as
+        // their error type may not be able to represent it
+        let check_capacity = (!transparent.is_present()).then(|| {
+            quote! {
+                #[inline]
+                fn check_capacity<__B: recode::bytes::BufMut>(
+                    #input_name: &#input_type,
+                    #buf_name: &__B,
+                ) -> Result<(), Self::Error> {
+                    use recode::util::BufMutExt as _;
+
+                    #buf_name.require_capacity(Self::size_of(#input_name))?;
+
+                    Ok(())
+                }
+            }
+        });
+
+        let packing = Packing {
+            bits: *bits,
+            order: bit_order.unwrap_or_default(),
+            endian: self.endian,
+        };
+
+        let mut bounds;
+        let (encode_body, size_body) = match data {
+            | Data::Struct(fields) => {
+                if transparent.is_present() {
+                    let [field] = fields.fields.as_slice() else {
+                        tokens.extend(
+                            darling::Error::custom(
+                                "transparent structs must have exactly one \
+                                 field",
+                            )
+                            .with_span(ident)
+                            .write_errors(),
+                        );
+                        return;
+                    };
+
+                    bounds = field.size_bounds();
+
+                    if self.encoder.error.is_none() {
+                        let ty = field.wire_type();
+                        let with = field.encoder.with.as_ref().unwrap_or(ty);
+
+                        error = match ctx {
+                            | Some(ctx) => syn::parse_quote! {
+                                <#with as recode::EncodeWith<#ctx, #ty>>::Error
+                            },
+                            | None => syn::parse_quote! {
+                                <#with as recode::Encoder<#ty>>::Error
+                            },
+                        };
+                    }
+                } else {
+                    bounds = to_size_bounds(fields, packing);
+                }
+
+                let field_stmts =
+                    to_encode_stmts(fields, &buf_name, ctx.as_ref(), packing);
+                let field_sizes = to_size_exprs(fields, ctx.as_ref(), packing);
+                let pattern = to_pattern(fields, quote!(#input_type));
 
-        let field_names = fields.iter().map(|&f| &f.ident);
-        let field_stmts = fields.iter().map(|&f| f.to_encode_stmt(&buf_name));
-        let field_sizes = fields.iter().map(|&f| f.to_size_expr(&input_name));
+                (
+                    quote! {
+                        let #pattern = #input_name;
+
+                        #field_stmts
+                    },
+                    quote! {
+                        let #pattern = #input_name;
+
+                        0 #( + #field_sizes )*
+                    },
+                )
+            }
+            | Data::Enum(variants) => {
+                let Some(tag) = tag else {
+                    tokens.extend(
+                        darling::Error::missing_field("tag")
+                            .with_span(ident)
+                            .write_errors(),
+                    );
+                    return;
+                };
+
+                let others: Vec<_> = variants
+                    .iter()
+                    .filter(|v| v.other.is_present())
+                    .map(|v| (&v.ident, v.fields.len()))
+                    .collect();
+
+                if let Err(e) = check_other(&others) {
+                    tokens.extend(e.write_errors());
+                    return;
+                }
+
+                bounds = SizeBounds::sum([
+                    SizeBounds::of(&quote!(recode::Encoder), tag, item),
+                    SizeBounds::any(variants.iter().map(|v| {
+                        match v.other.is_present() {
+                            | true => SizeBounds::exact(0),
+                            | false => to_size_bounds(&v.fields, packing),
+                        }
+                    })),
+                ]);
+
+                let encode_arms = variants.iter().map(|v| {
+                    v.to_encode_arm(
+                        tag,
+                        item,
+                        &input_type,
+                        &buf_name,
+                        ctx.as_ref(),
+                        packing,
+                    )
+                });
+                let size_arms = variants.iter().map(|v| {
+                    v.to_size_arm(tag, item, &input_type, ctx.as_ref(), packing)
+                });
+
+                (
+                    quote! {
+                        match #input_name {
+                            #( #encode_arms )*
+                        }
+                    },
+                    quote! {
+                        match #input_name {
+                            #( #size_arms )*
+                        }
+                    },
+                )
+            }
+        };
 
         let (imp, ty, wher) = generics.split_for_impl();
 
-        tokens.extend(quote::quote! {
-            impl #imp recode::Encoder<#input_type> for #ident #ty #wher {
-                type Error = #error;
+        let (put_magic, size_body) = match magic {
+            | Some(magic) => {
+                let len = magic.value().len();
 
-                fn encode(
-                    #input_name: &#input_type,
-                    #buf_name: &mut recode::bytes::BytesMut,
-                ) -> Result<(), Self::Error> {
-                    use recode::Encoder;
+                bounds = SizeBounds::sum([SizeBounds::exact(len), bounds]);
+
+                (
+                    quote!(recode::bytes::BufMut::put_slice(#buf_name, #magic);),
+                    quote!(#len + { #size_body }),
+                )
+            }
+            | None => (TokenStream::new(), size_body),
+        };
+        let encode_body = quote! {
+            use recode::Encoder;
 
-                    let #input_type {
-                        #( ref #field_names, )*
-                    } = #input_name;
+            #put_magic
 
-                    #( #field_stmts )*
+            #encode_body
 
-                    Ok(())
+            Ok(())
+        };
+
+        let consts = bounds.to_consts();
+
+        // with a context type, the value can only be encoded through
+        // `EncodeWith`, which is what the fields of the container are
+        // encoded with as well
+        tokens.extend(match ctx {
+            | Some(ctx) => quote! {
+                impl #imp recode::EncodeWith<#ctx, #input_type>
+                    for #ident #ty #wher
+                {
+                    type Error = #error;
+
+                    #[allow(unused_variables)]
+                    fn encode_with<__B: recode::bytes::BufMut>(
+                        #input_name: &#input_type,
+                        #buf_name: &mut __B,
+                        __ctx: &#ctx,
+                    ) -> Result<(), Self::Error> {
+                        let ctx = __ctx;
+
+                        #encode_body
+                    }
+
+                    #[allow(unused_variables)]
+                    fn size_of_with(
+                        #input_name: &#input_type,
+                        __ctx: &#ctx,
+                    ) -> usize {
+                        let ctx = __ctx;
+
+                        #size_body
+                    }
                 }
+            },
+            | None => quote! {
+                impl #imp recode::Encoder<#input_type> for #ident #ty #wher {
+                    type Error = #error;
+
+                    #consts
 
-                fn size_of(#input_name: &#input_type) -> usize {
-                    0 #( + #field_sizes )*
+                    #[allow(unused_variables)]
+                    fn encode<__B: recode::bytes::BufMut>(
+                        #input_name: &#input_type,
+                        #buf_name: &mut __B,
+                    ) -> Result<(), Self::Error> {
+                        #encode_body
+                    }
+
+                    #[allow(unused_variables)]
+                    fn size_of(#input_name: &#input_type) -> usize {
+                        #size_body
+                    }
+
+                    #check_capacity
                 }
+            },
+        });
+    }
+}
+
+impl Encoder {
+    /// Returns the tag of the input, which defaults to the `repr` type of
+    /// C-like enums.
+    fn tag(&self) -> Option<syn::Type> {
+        self.tag
+            .clone()
+            .or_else(|| repr_tag(self.repr.as_ref(), &self.attrs, self.endian))
+    }
+
+    /// Returns the type the tag of the input is encoded from.
+    pub(crate) fn tag_item(&self) -> syn::Type {
+        tag_item(self.tag.as_ref(), self.repr.as_ref(), &self.attrs)
+    }
+
+    /// Returns the fields of the input, where numeric fields without an
+    /// explicit codec use the container's byte order, and the ids of its
+    /// variants.
+    fn data(&self) -> Data<EncoderVariant, EncoderField> {
+        let mut data = self.data.clone();
+
+        if let Data::Enum(ref mut variants) = data {
+            resolve_ids(
+                variants
+                    .iter_mut()
+                    .map(|v| (&mut v.id, v.discriminant.as_ref())),
+            );
+        }
+
+        let Some(endian) = self.endian else {
+            return data;
+        };
+
+        let fields: Vec<&mut EncoderField> = match data {
+            | Data::Struct(ref mut fields) => {
+                fields.fields.iter_mut().collect()
+            }
+            | Data::Enum(ref mut variants) => variants
+                .iter_mut()
+                .flat_map(|v| v.fields.fields.iter_mut())
+                .collect(),
+        };
+
+        for field in fields {
+            if field.encoder.with.is_none() {
+                field.encoder.with = endian.codec_for(field.wire_type());
             }
 
+            field.before.set_endian(endian);
+            field.after.set_endian(endian);
+        }
+
+        data
+    }
+
+    /// Generates a `ToBits` implementation for a `bits` enum, which converts
+    /// its unit variants to their ids.
+    fn to_to_bits(&self, variants: &[EncoderVariant]) -> TokenStream {
+        use quote::quote;
+
+        if let Some(v) = variants.iter().find(|v| !v.fields.is_unit()) {
+            return darling::Error::custom(
+                "variants of `bits` enums cannot have fields",
+            )
+            .with_span(&v.ident)
+            .write_errors();
+        }
+
+        let ident = &self.ident;
+        let (imp, ty, wher) = self.generics.split_for_impl();
+        let arms = variants.iter().map(|EncoderVariant { ident, id, .. }| {
+            quote!(Self::#ident => (#id) as u64,)
         });
+
+        quote! {
+            impl #imp recode::bits::ToBits for #ident #ty #wher {
+                fn to_bits(&self) -> u64 {
+                    match self {
+                        #( #arms )*
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl EncoderVariant {
+    fn to_encode_arm(
+        &self,
+        tag: &syn::Type,
+        tag_item: &syn::Type,
+        input_type: &syn::Type,
+        buf_ident: &syn::Ident,
+        ctx: Option<&syn::Type>,
+        packing: Packing,
+    ) -> TokenStream {
+        let EncoderVariant {
+            ident, fields, id, ..
+        } = self;
+
+        let pattern = to_pattern(fields, quote::quote!(#input_type::#ident));
+
+        if self.other.is_present() {
+            let convert = self.to_other_id(tag_item);
+
+            return quote::quote! {
+                #pattern => {
+                    <#tag as recode::Encoder<#tag_item>>::encode(
+                        &#convert?,
+                        #buf_ident,
+                    )?;
+                }
+            };
+        }
+
+        let field_stmts = to_encode_stmts(fields, buf_ident, ctx, packing);
+
+        quote::quote! {
+            #pattern => {
+                <#tag as recode::Encoder<#tag_item>>::encode(
+                    &((#id) as #tag_item),
+                    #buf_ident,
+                )?;
+
+                #field_stmts
+            }
+        }
+    }
+
+    fn to_size_arm(
+        &self,
+        tag: &syn::Type,
+        tag_item: &syn::Type,
+        input_type: &syn::Type,
+        ctx: Option<&syn::Type>,
+        packing: Packing,
+    ) -> TokenStream {
+        let EncoderVariant {
+            ident, fields, id, ..
+        } = self;
+
+        let pattern = to_pattern(fields, quote::quote!(#input_type::#ident));
+
+        // a failed conversion is reported by `encode`
+        if self.other.is_present() {
+            let convert = self.to_other_id(tag_item);
+
+            return quote::quote! {
+                #pattern => match #convert {
+                    | Ok(__tag) => {
+                        <#tag as recode::Encoder<#tag_item>>::size_of(&__tag)
+                    }
+                    | Err(_) => 0,
+                },
+            };
+        }
+
+        let field_sizes = to_size_exprs(fields, ctx, packing);
+
+        quote::quote! {
+            #pattern => {
+                <#tag as recode::Encoder<#tag_item>>::size_of(
+                    &((#id) as #tag_item),
+                )
+                    #( + #field_sizes )*
+            }
+        }
+    }
+}
+
+impl EncoderVariant {
+    /// Generates an expression that converts the field of the `other` variant
+    /// into a `Result<tag_item, recode::Error>`.
+    fn to_other_id(&self, tag_item: &syn::Type) -> TokenStream {
+        let field = &self.fields.fields[0];
+        let (_, binding) = field_binding(field.ident.as_ref(), 0);
+        let name = field_name(field.ident.as_ref(), 0);
+        Conversion::TryFrom(tag_item).to_wire(
+            &field.ty,
+            &quote::quote!(#binding),
+            &name,
+        )
     }
 }
 
 impl EncoderField {
+    /// Returns how the field is converted into the type it is encoded as.
+    pub(crate) fn conversion(&self) -> darling::Result<Option<Conversion<'_>>> {
+        Conversion::new(self.via.as_ref(), self.try_from.as_ref())
+    }
+
+    /// Returns the type the field is encoded as.
+    pub(crate) fn wire_type(&self) -> &syn::Type {
+        match self.conversion() {
+            | Ok(Some(conversion)) => conversion.wire_type(),
+            | _ => &self.ty,
+        }
+    }
+
+    /// Returns a reference to the value that is encoded for the field bound
+    /// to `binding`, after `map` or `map_encode` is applied.
+    fn to_input(&self, binding: &syn::Ident) -> proc_macro2::TokenStream {
+        use quote::quote;
+
+        match (&self.encoder.map, &self.encoder.map_encode) {
+            | (Some(map), _) => quote!((#map)(#binding)),
+            | (None, Some(map)) => quote!(&(#map)(#binding)),
+            | (None, None) => quote!(#binding),
+        }
+    }
+
+    /// Generates the statement that encodes the field `name`, bound to
+    /// `binding`.
     pub(crate) fn to_encode_stmt(
         &self,
+        binding: &syn::Ident,
         buf_ident: &syn::Ident,
+        name: &str,
+        ctx: Option<&syn::Type>,
     ) -> proc_macro2::TokenStream {
         use quote::quote;
 
         let EncoderField {
-            ident,
+            ident: _,
             ty,
+            before: _,
+            after: _,
+            bits: _,
+            via: _,
+            try_from: _,
             encoder:
                 EncoderFieldOpts {
                     skip,
                     skip_if,
-                    map,
+                    map: _,
+                    map_encode: _,
                     with,
                     size: _,
                     validate,
+                    value,
                 },
         } = self;
 
@@ -140,18 +622,49 @@ impl EncoderField {
             return Default::default();
         }
 
-        let with = with.as_ref().unwrap_or(ty);
-        let input = map
-            .as_ref()
-            .map(|m| quote! (((#m)(#ident))))
-            .unwrap_or(quote! (#ident));
+        let conversion = match self.conversion() {
+            | Ok(conversion) => conversion,
+            | Err(e) => return e.write_errors(),
+        };
+        let wire = self.wire_type();
+        let with = with.as_ref().unwrap_or(wire);
+        let input = self.to_input(binding);
         let validate = validate
             .as_ref()
-            .map(|v| quote!((#v)(#ident, #buf_ident)?;))
+            .map(|v| quote!((#v)(#binding, #buf_ident)?;))
             .unwrap_or(TokenStream::new());
-        let stmt = quote! {
-            #validate
-            <#with as recode::Encoder<#ty>>::encode(#input, #buf_ident)?;
+        let stmt = match conversion {
+            | Some(conversion) => {
+                let convert = conversion.to_wire(ty, &input, name);
+                let encode =
+                    encode_call(with, wire, &quote!(&__wire), buf_ident, ctx);
+
+                quote! {
+                    #validate
+
+                    let __wire = #convert?;
+
+                    #encode?;
+                }
+            }
+            | None => {
+                let encode = encode_call(with, ty, &input, buf_ident, ctx);
+
+                quote! {
+                    #validate
+                    #encode?;
+                }
+            }
+        };
+        let stmt = match value {
+            | Some(value) => quote! {
+                {
+                    let #binding = &(#value);
+
+                    #stmt
+                }
+            },
+            | None => stmt,
         };
 
         skip_if
@@ -160,22 +673,328 @@ impl EncoderField {
             .unwrap_or(stmt)
     }
 
+    /// Returns how the field is laid out.
+    fn layout(&self) -> FieldLayout {
+        match self.bits {
+            | _ if self.encoder.skip.is_present() => FieldLayout::Skipped,
+            | Some(bits) => FieldLayout::Bits(bits),
+            | None => FieldLayout::Bytes,
+        }
+    }
+
+    /// Returns the bounds of the number of bytes the field is encoded into.
+    fn size_bounds(&self) -> SizeBounds {
+        let EncoderFieldOpts {
+            skip,
+            skip_if,
+            with,
+            size,
+            ..
+        } = &self.encoder;
+
+        if skip.is_present() {
+            return SizeBounds::exact(0);
+        }
+
+        if size.is_some() {
+            return SizeBounds::unbounded();
+        }
+
+        let ty = self.wire_type();
+        let bounds = SizeBounds::of(
+            &quote::quote!(recode::Encoder),
+            with.as_ref().unwrap_or(ty),
+            ty,
+        );
+
+        match skip_if {
+            | Some(_) => bounds.optional(),
+            | None => bounds,
+        }
+    }
+
     pub(crate) fn to_size_expr(
         &self,
-        input_ident: &syn::Ident,
+        binding: &syn::Ident,
+        ctx: Option<&syn::Type>,
     ) -> proc_macro2::TokenStream {
         use quote::quote;
 
-        if let Some(ref expr) = self.encoder.size {
-            return expr.to_token_stream();
+        if self.encoder.skip.is_present() {
+            return quote!(0);
+        }
+
+        let size = if let Some(ref expr) = self.encoder.size {
+            expr.to_token_stream()
+        } else {
+            let ty = self.wire_type();
+            let with = self.encoder.with.as_ref().unwrap_or(ty);
+            let input = self.to_input(binding);
+
+            match self.conversion() {
+                // a failed conversion is reported by `encode`
+                | Ok(Some(conversion)) => {
+                    let convert = conversion.to_wire(&self.ty, &input, "");
+                    let size = size_of_call(with, ty, &quote!(&__wire), ctx);
+
+                    quote! {
+                        match #convert {
+                            | Ok(__wire) => #size,
+                            | Err(_) => 0,
+                        }
+                    }
+                }
+                | _ => size_of_call(with, ty, &input, ctx),
+            }
+        };
+        let size = match self.encoder.value {
+            | Some(ref value) => quote! {
+                {
+                    let #binding = &(#value);
+
+                    #size
+                }
+            },
+            | None => size,
+        };
+
+        self.encoder
+            .skip_if
+            .as_ref()
+            .map(|s| quote!((if (#s) { 0 } else { #size })))
+            .unwrap_or(size)
+    }
+}
+
+/// Generates a pattern that binds every field of `path` by reference.
+fn to_pattern(fields: &Fields<EncoderField>, path: TokenStream) -> TokenStream {
+    let bindings = fields.iter().enumerate().map(|(i, f)| {
+        let (member, binding) = field_binding(f.ident.as_ref(), i);
+
+        quote::quote!(#member: ref #binding)
+    });
+
+    quote::quote! {
+        #path {
+            #( #bindings, )*
+        }
+    }
+}
+
+/// Generates the statements that encode the bound `fields`.
+fn to_encode_stmts(
+    fields: &Fields<EncoderField>,
+    buf_ident: &syn::Ident,
+    ctx: Option<&syn::Type>,
+    packing: Packing,
+) -> TokenStream {
+    let layouts: Vec<_> = fields.iter().map(EncoderField::layout).collect();
+    let runs = match to_runs(&layouts, packing) {
+        | Ok(runs) => runs,
+        | Err(e) => return darling::Error::custom(e).write_errors(),
+    };
+
+    runs.iter()
+        .map(|run| match *run {
+            | Run::Field(i) => {
+                let f = &fields.fields[i];
+                let (_, binding) = field_binding(f.ident.as_ref(), i);
+                let name = field_name(f.ident.as_ref(), i);
+                let before = to_filler_stmts(&f.before, buf_ident);
+                let after = to_filler_stmts(&f.after, buf_ident);
+                let encode = f.to_encode_stmt(&binding, buf_ident, &name, ctx);
+
+                quote::quote! {
+                    #before
+                    #encode
+                    #after
+                }
+            }
+            | Run::Bits {
+                fields: ref run,
+                width,
+            } => {
+                let fillers = run.iter().flat_map(|&(i, _)| {
+                    [&fields.fields[i].before, &fields.fields[i].after]
+                });
+
+                match check_bits_fillers(fillers) {
+                    | Ok(()) => {
+                        to_bits_stmt(fields, run, width, buf_ident, packing)
+                    }
+                    | Err(e) => e.write_errors(),
+                }
+            }
+        })
+        .collect()
+}
+
+/// Generates the statements that encode `filler`.
+fn to_filler_stmts(filler: &Filler, buf_ident: &syn::Ident) -> TokenStream {
+    use quote::quote;
+
+    filler
+        .0
+        .iter()
+        .map(|item| match item {
+            | FillerItem::Magic(magic) => quote! {
+                recode::bytes::BufMut::put_slice(#buf_ident, #magic);
+            },
+            | FillerItem::Constant(constant) => {
+                let FillerConstant { ty, value, with } = &**constant;
+
+                let with = with.as_ref().unwrap_or(ty);
+                let encode =
+                    encode_call(with, ty, &quote!(&(#value)), buf_ident, None);
+
+                quote!(#encode?;)
+            }
+            | FillerItem::Reserved(bytes) => quote! {
+                recode::bytes::BufMut::put_bytes(#buf_ident, 0, #bytes);
+            },
+        })
+        .collect()
+}
+
+/// Generates an expression that returns the number of bytes of `filler`.
+fn to_filler_size(filler: &Filler) -> TokenStream {
+    use quote::quote;
+
+    let sizes = filler.0.iter().map(|item| match item {
+        | FillerItem::Magic(magic) => {
+            let len = magic.value().len();
+
+            quote!(#len)
+        }
+        | FillerItem::Constant(constant) => {
+            let FillerConstant { ty, value, with } = &**constant;
+
+            size_of_call(
+                with.as_ref().unwrap_or(ty),
+                ty,
+                &quote!(&(#value)),
+                None,
+            )
         }
+        | FillerItem::Reserved(bytes) => quote!(#bytes),
+    });
 
-        let ident = self.ident.as_ref();
-        let ty = &self.ty;
-        let with = self.encoder.with.as_ref().unwrap_or(&self.ty);
+    quote!(0 #( + #sizes )*)
+}
+
+/// Generates the statement that encodes a run of bit fields into a backing
+/// integer that is `width` bits wide.
+fn to_bits_stmt(
+    fields: &Fields<EncoderField>,
+    run: &[(usize, u32)],
+    width: u32,
+    buf_ident: &syn::Ident,
+    packing: Packing,
+) -> TokenStream {
+    use quote::quote;
+
+    let (backing, codec) = packing.backing(width);
+    let order = packing.order;
+    let writes = run.iter().map(|&(i, bits)| {
+        let field = &fields.fields[i];
+        let (_, binding) = field_binding(field.ident.as_ref(), i);
+        let input = field
+            .encoder
+            .value
+            .as_ref()
+            .map(|value| quote!(&(#value)))
+            .unwrap_or(quote!(#binding));
+        let validate = field
+            .encoder
+            .validate
+            .as_ref()
+            .map(|v| quote!((#v)(#binding, #buf_ident)?;));
+        let write = match field.conversion() {
+            | Ok(Some(conversion)) => {
+                let name = field_name(field.ident.as_ref(), i);
+                let convert = conversion.to_wire(&field.ty, &input, &name);
+
+                quote!(__bits.write(&#convert?, #bits)?;)
+            }
+            | Ok(None) => quote!(__bits.write(#input, #bits)?;),
+            | Err(e) => e.write_errors(),
+        };
 
         quote! {
-            <#with as recode::Encoder<#ty>>::size_of(&#input_ident.#ident)
+            #validate
+            #write
+        }
+    });
+
+    quote! {
+        {
+            let mut __bits = recode::bits::BitWriter::new(#width, #order);
+
+            #( #writes )*
+
+            <#codec as recode::Encoder<#backing>>::encode(
+                &__bits.finish::<#backing>(),
+                #buf_ident,
+            )?;
         }
     }
 }
+
+/// Returns the bounds of the number of bytes `fields` are encoded into.
+fn to_size_bounds(
+    fields: &Fields<EncoderField>,
+    packing: Packing,
+) -> SizeBounds {
+    let layouts: Vec<_> = fields.iter().map(EncoderField::layout).collect();
+
+    // invalid layouts are reported by `to_encode_stmts`
+    let runs = to_runs(&layouts, packing).unwrap_or_default();
+
+    SizeBounds::of_runs(&runs, |i| {
+        let field = &fields.fields[i];
+        let codec = quote::quote!(recode::Encoder);
+
+        SizeBounds::sum([
+            field.before.size_bounds(&codec),
+            field.size_bounds(),
+            field.after.size_bounds(&codec),
+        ])
+    })
+}
+
+/// Returns the size expressions of the bound `fields`.
+fn to_size_exprs(
+    fields: &Fields<EncoderField>,
+    ctx: Option<&syn::Type>,
+    packing: Packing,
+) -> Vec<TokenStream> {
+    let layouts: Vec<_> = fields.iter().map(EncoderField::layout).collect();
+
+    // invalid layouts are reported by `to_encode_stmts`
+    let runs = to_runs(&layouts, packing).unwrap_or_default();
+
+    runs.iter()
+        .map(|run| match *run {
+            | Run::Field(i) => {
+                let f = &fields.fields[i];
+                let (_, binding) = field_binding(f.ident.as_ref(), i);
+                let size = f.to_size_expr(&binding, ctx);
+
+                match (f.before.is_empty(), f.after.is_empty()) {
+                    | (true, true) => size,
+                    | _ => {
+                        let before = to_filler_size(&f.before);
+                        let after = to_filler_size(&f.after);
+
+                        quote::quote!((#before + #size + #after))
+                    }
+                }
+            }
+            | Run::Bits { width, .. } => {
+                let len = width as usize / 8;
+
+                quote::quote!(#len)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,275 @@
+use darling::{
+    ast::{Data, Fields},
+    util::Flag,
+};
+use proc_macro2::TokenStream;
+
+use crate::util::*;
+
+/// The input of the `FixedSize` derive.
+///
+/// Only the attributes that affect the layout of the struct are read, as it is
+/// usually derived along with `Decoder`/`Encoder` (or `Recode`), which check
+/// the others.
+#[derive(Debug, darling::FromDeriveInput)]
+#[darling(attributes(recode), supports(struct_any), allow_unknown_fields)]
+pub(crate) struct FixedSize {
+    ident: syn::Ident,
+    generics: syn::Generics,
+    data: Data<(), FixedSizeField>,
+    endian: Option<Endian>,
+    magic: Option<syn::LitByteStr>,
+    bits: Option<u32>,
+}
+
+#[derive(Debug, darling::FromField)]
+#[darling(attributes(recode), allow_unknown_fields)]
+pub(crate) struct FixedSizeField {
+    ty: syn::Type,
+    bits: Option<u32>,
+    via: Option<syn::Type>,
+    try_from: Option<syn::Type>,
+    skip: Flag,
+    skip_if: Option<syn::Expr>,
+    with: Option<syn::Type>,
+    #[darling(default)]
+    before: Filler,
+    #[darling(default)]
+    after: Filler,
+    #[darling(default)]
+    decoder: FixedSizeFieldOpts,
+    #[darling(default)]
+    encoder: FixedSizeFieldOpts,
+}
+
+/// The options of `decoder(...)` and `encoder(...)` that affect the size of a
+/// field.
+#[derive(Debug, Default, darling::FromMeta)]
+#[darling(default, allow_unknown_fields)]
+pub(crate) struct FixedSizeFieldOpts {
+    skip: Flag,
+    skip_if: Option<syn::Expr>,
+    with: Option<syn::Type>,
+    len: Option<syn::Expr>,
+    count: Option<syn::Expr>,
+    size: Option<syn::Expr>,
+}
+
+impl darling::ToTokens for FixedSize {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        use quote::quote;
+
+        let FixedSize {
+            ident,
+            generics,
+            data,
+            endian,
+            magic,
+            bits,
+        } = self;
+
+        let Data::Struct(fields) = data else {
+            unreachable!("only structs are supported");
+        };
+
+        let packing = Packing {
+            bits: *bits,
+            order: BitOrder::default(),
+            endian: *endian,
+        };
+        let sizes = match to_sizes(fields, packing, *endian) {
+            | Ok(sizes) => sizes,
+            | Err(e) => {
+                tokens.extend(e.write_errors());
+                return;
+            }
+        };
+        let magic = magic.as_ref().map_or(0, |magic| magic.value().len());
+        let (imp, ty, wher) = generics.split_for_impl();
+
+        tokens.extend(quote! {
+            impl #imp recode::FixedSize for #ident #ty #wher {
+                const SIZE: usize = #magic #( + #sizes )*;
+            }
+        });
+    }
+}
+
+impl FixedSizeField {
+    /// Returns the size of the field, or an error if it is not fixed.
+    fn to_size(&self, endian: Option<Endian>) -> darling::Result<TokenStream> {
+        use quote::quote;
+
+        let FixedSizeField {
+            ty,
+            bits: _,
+            via: _,
+            try_from: _,
+            skip: _,
+            skip_if,
+            with: _,
+            before: _,
+            after: _,
+            decoder,
+            encoder,
+        } = self;
+
+        let variable = [skip_if, &decoder.skip_if, &encoder.skip_if]
+            .into_iter()
+            .any(Option::is_some)
+            || [&decoder.len, &decoder.count, &encoder.size]
+                .into_iter()
+                .any(Option::is_some);
+
+        if variable {
+            return Err(darling::Error::custom(
+                "fields with `skip_if`, `len`, `count` or `size` do not have \
+                 a fixed size",
+            )
+            .with_span(ty));
+        }
+
+        Ok(match self.codec(endian)? {
+            | Some(with) => quote!(<#with as recode::FixedSize>::SIZE),
+            | None => quote!(0),
+        })
+    }
+
+    /// Returns the codec of the field, or `None` if it is skipped.
+    ///
+    /// Fails if the field is not laid out the same way when decoding and
+    /// encoding, as it would not have a single size.
+    fn codec(
+        &self,
+        endian: Option<Endian>,
+    ) -> darling::Result<Option<syn::Type>> {
+        use quote::ToTokens;
+
+        let decoder = self.side_codec(&self.decoder, endian)?;
+        let encoder = self.side_codec(&self.encoder, endian)?;
+
+        let same = match (&decoder, &encoder) {
+            | (Some(decoder), Some(encoder)) => {
+                decoder.to_token_stream().to_string()
+                    == encoder.to_token_stream().to_string()
+            }
+            | (decoder, encoder) => decoder.is_none() && encoder.is_none(),
+        };
+
+        if !same {
+            return Err(darling::Error::custom(
+                "fields must be decoded and encoded the same way to have a \
+                 fixed size",
+            )
+            .with_span(&self.ty));
+        }
+
+        Ok(decoder)
+    }
+
+    /// Returns the codec of the field on the side with the options `opts`, or
+    /// `None` if it is skipped on that side.
+    fn side_codec(
+        &self,
+        opts: &FixedSizeFieldOpts,
+        endian: Option<Endian>,
+    ) -> darling::Result<Option<syn::Type>> {
+        if self.skip.is_present() || opts.skip.is_present() {
+            return Ok(None);
+        }
+
+        let ty = Conversion::new(self.via.as_ref(), self.try_from.as_ref())?
+            .map_or(&self.ty, Conversion::wire_type);
+        let with = opts
+            .with
+            .clone()
+            .or_else(|| self.with.clone())
+            .or_else(|| endian.and_then(|e| e.codec_for(ty)))
+            .unwrap_or_else(|| ty.clone());
+
+        Ok(Some(with))
+    }
+
+    /// Returns how the field is laid out.
+    fn layout(&self, endian: Option<Endian>) -> darling::Result<FieldLayout> {
+        Ok(match self.bits {
+            | _ if self.codec(endian)?.is_none() => FieldLayout::Skipped,
+            | Some(bits) => FieldLayout::Bits(bits),
+            | None => FieldLayout::Bytes,
+        })
+    }
+}
+
+/// Returns the sizes of the runs of `fields`.
+fn to_sizes(
+    fields: &Fields<FixedSizeField>,
+    packing: Packing,
+    endian: Option<Endian>,
+) -> darling::Result<Vec<TokenStream>> {
+    let mut errors = darling::Error::accumulator();
+    let layouts: Vec<_> = fields
+        .iter()
+        .map(|field| {
+            errors
+                .handle(field.layout(endian))
+                .unwrap_or(FieldLayout::Skipped)
+        })
+        .collect();
+    let runs = errors
+        .handle(to_runs(&layouts, packing).map_err(darling::Error::custom))
+        .unwrap_or_default();
+
+    let sizes = runs
+        .iter()
+        .filter_map(|run| match *run {
+            | Run::Field(i) => {
+                let field = &fields.fields[i];
+                let size = errors.handle(field.to_size(endian))?;
+                let before = to_filler_sizes(&field.before, endian);
+                let after = to_filler_sizes(&field.after, endian);
+
+                Some(quote::quote!(#( #before + )* #size #( + #after )*))
+            }
+            | Run::Bits {
+                fields: ref run,
+                width,
+            } => {
+                let fillers = run.iter().flat_map(|&(i, _)| {
+                    [&fields.fields[i].before, &fields.fields[i].after]
+                });
+                let len = width as usize / 8;
+
+                errors.handle(check_bits_fillers(fillers))?;
+
+                Some(quote::quote!(#len))
+            }
+        })
+        .collect();
+
+    errors.finish_with(sizes)
+}
+
+/// Returns the sizes of the items of `filler`.
+fn to_filler_sizes(
+    filler: &Filler,
+    endian: Option<Endian>,
+) -> impl Iterator<Item = TokenStream> + '_ {
+    filler.0.iter().map(move |item| match item {
+        | FillerItem::Magic(magic) => {
+            let len = magic.value().len();
+
+            quote::quote!(#len)
+        }
+        | FillerItem::Constant(constant) => {
+            let FillerConstant { ty, with, .. } = &**constant;
+
+            let with = with
+                .clone()
+                .or_else(|| endian.and_then(|e| e.codec_for(ty)))
+                .unwrap_or_else(|| ty.clone());
+
+            quote::quote!(<#with as recode::FixedSize>::SIZE)
+        }
+        | FillerItem::Reserved(bytes) => quote::quote!(#bytes),
+    })
+}
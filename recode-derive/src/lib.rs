@@ -1,5 +1,7 @@
 mod decoder;
 mod encoder;
+mod fixed_size;
+mod raw_decoder;
 mod recode;
 mod util;
 
@@ -25,6 +27,16 @@ pub fn derive_encoder(input: TokenStream) -> TokenStream {
     emit_impl_or_error!(encoder::Encoder[input]).into()
 }
 
+#[proc_macro_derive(FixedSize, attributes(recode))]
+pub fn derive_fixed_size(input: TokenStream) -> TokenStream {
+    emit_impl_or_error!(fixed_size::FixedSize[input]).into()
+}
+
+#[proc_macro_derive(RawDecoder, attributes(recode))]
+pub fn derive_raw_decoder(input: TokenStream) -> TokenStream {
+    emit_impl_or_error!(raw_decoder::RawDecoder[input]).into()
+}
+
 #[proc_macro_derive(Recode, attributes(recode))]
 pub fn derive_recode(input: TokenStream) -> TokenStream {
     emit_impl_or_error!(recode::Recode[input]).into()
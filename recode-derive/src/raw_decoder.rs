@@ -0,0 +1,587 @@
+use darling::{ast::Data, FromDeriveInput};
+use proc_macro2::TokenStream;
+use syn::ext::IdentExt;
+
+use crate::{
+    decoder::{
+        context_tokens,
+        to_constructor,
+        Decoder,
+        DecoderField,
+        DecoderFieldOpts,
+        DecoderOpts,
+        DecoderVariant,
+    },
+    recode::Recode,
+    util::*,
+};
+
+/// The input of the `RawDecoder` derive.
+///
+/// This accepts the attributes of both the `Decoder` and `Recode` derives, so
+/// it can be derived along with either of them. Types without lifetimes
+/// implement `RawDecoder`, while types with a lifetime implement
+/// `BorrowDecoder` for it, so that their fields can borrow from the slice.
+#[derive(Debug)]
+pub(crate) struct RawDecoder(Decoder);
+
+impl FromDeriveInput for RawDecoder {
+    fn from_derive_input(input: &syn::DeriveInput) -> darling::Result<Self> {
+        Recode::from_derive_input(input).map(|r| Self(r.to_decoder()))
+    }
+}
+
+/// How the fields of the type are decoded.
+#[derive(Clone, Copy)]
+enum Mode<'a> {
+    /// With `RawDecoder`, for types without lifetimes.
+    Owned,
+    /// With `BorrowDecoder`, for types that borrow for the given lifetime.
+    Borrowed(&'a syn::Lifetime),
+}
+
+impl Mode<'_> {
+    /// Generates a call that decodes a `ty` from the slice `rest` with the
+    /// codec `with`.
+    fn decode_call(
+        self,
+        with: &syn::Type,
+        ty: &syn::Type,
+        rest: &TokenStream,
+    ) -> TokenStream {
+        match self {
+            | Mode::Owned => quote::quote! {
+                <#with as recode::RawDecoder<#ty>>::raw_decode(#rest)
+            },
+            | Mode::Borrowed(lt) => quote::quote! {
+                <#with as recode::BorrowDecoder<#lt, #ty>>::borrow_decode(#rest)
+            },
+        }
+    }
+}
+
+impl darling::ToTokens for RawDecoder {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        use quote::quote;
+
+        let data = &self.0.data();
+        let tag = &self.0.tag();
+        let item = &self.0.tag_item();
+        let Decoder {
+            ident,
+            generics,
+            attrs: _,
+            data: _,
+            tag: _,
+            repr: _,
+            transparent,
+            endian: _,
+            ctx,
+            magic,
+            bits,
+            bit_order: _,
+            decoder:
+                DecoderOpts {
+                    disable,
+                    output_type,
+                    error,
+                    buffer_name,
+                },
+        } = &self.0;
+
+        if disable.is_present() {
+            return;
+        }
+
+        if let Some(ctx) = ctx {
+            tokens.extend(
+                darling::Error::custom(
+                    "`RawDecoder` cannot be derived for types with a context",
+                )
+                .with_span(ctx)
+                .write_errors(),
+            );
+            return;
+        }
+
+        if bits.is_some() {
+            tokens.extend(
+                darling::Error::custom(
+                    "`RawDecoder` cannot be derived for `bits` types",
+                )
+                .with_span(ident)
+                .write_errors(),
+            );
+            return;
+        }
+
+        let lifetimes: Vec<_> = generics.lifetimes().collect();
+        let mode = match lifetimes.as_slice() {
+            | [] => Mode::Owned,
+            | [lt] => Mode::Borrowed(&lt.lifetime),
+            | [_, lt, ..] => {
+                tokens.extend(
+                    darling::Error::custom(
+                        "`RawDecoder` can only be derived for types with at \
+                         most one lifetime",
+                    )
+                    .with_span(&lt.lifetime)
+                    .write_errors(),
+                );
+                return;
+            }
+        };
+
+        let output_type = output_type
+            .clone()
+            .unwrap_or(syn::Type::Verbatim(quote!(Self)));
+        let buffer_name = buffer_name.clone().unwrap_or(default_buffer_name());
+        let rest = quote::format_ident!("__rest");
+        let mut error = error.clone().unwrap_or(box_type());
+
+        let body = match data {
+            | Data::Struct(fields) => {
+                if transparent.is_present() {
+                    let [field] = fields.fields.as_slice() else {
+                        tokens.extend(
+                            darling::Error::custom(
+                                "transparent structs must have exactly one \
+                                 field",
+                            )
+                            .with_span(ident)
+                            .write_errors(),
+                        );
+                        return;
+                    };
+
+                    if self.0.decoder.error.is_none() {
+                        let ty = field.wire_type();
+                        let with = field.decoder.with.as_ref().unwrap_or(ty);
+
+                        error = match mode {
+                            | Mode::Owned => syn::parse_quote! {
+                                <#with as recode::RawDecoder<#ty>>::Error
+                            },
+                            | Mode::Borrowed(lt) => syn::parse_quote! {
+                                <#with as recode::BorrowDecoder<#lt, #ty>>::Error
+                            },
+                        };
+                    }
+                }
+
+                let context = (!transparent.is_present())
+                    .then(|| ident.unraw().to_string());
+                let stmts = fields.iter().enumerate().map(|(i, f)| {
+                    let (_, binding) = field_binding(f.ident.as_ref(), i);
+                    let name = field_name(f.ident.as_ref(), i);
+
+                    to_raw_decode_stmt(
+                        f,
+                        &binding,
+                        &rest,
+                        context.as_deref(),
+                        &name,
+                        mode,
+                    )
+                });
+                let constructor = to_constructor(fields, quote!(#output_type));
+
+                quote! {
+                    #( #stmts )*
+
+                    Ok((#constructor, __len - #rest.len()))
+                }
+            }
+            | Data::Enum(variants) => {
+                let Some(tag) = tag else {
+                    tokens.extend(
+                        darling::Error::missing_field("tag")
+                            .with_span(ident)
+                            .write_errors(),
+                    );
+                    return;
+                };
+
+                let others: Vec<_> = variants
+                    .iter()
+                    .filter(|v| v.other.is_present())
+                    .map(|v| (&v.ident, v.fields.len()))
+                    .collect();
+
+                if let Err(e) = check_other(&others) {
+                    tokens.extend(e.write_errors());
+                    return;
+                }
+
+                let name = ident.unraw().to_string();
+                let arms = variants
+                    .iter()
+                    .filter(|v| !v.other.is_present())
+                    .map(|v| {
+                        to_raw_decode_arm(
+                            v,
+                            &output_type,
+                            item,
+                            &rest,
+                            &name,
+                            mode,
+                        )
+                    });
+                let fallback =
+                    match variants.iter().find(|v| v.other.is_present()) {
+                        | Some(v) => {
+                            let value =
+                                v.to_other_value(&output_type, item, &name);
+
+                            quote!(Ok((#value, __len - #rest.len())))
+                        }
+                        | None => {
+                            let unknown = unknown_tag(item);
+
+                            quote! {
+                                Err(recode::error::add_context::<Self::Error>(
+                                    #unknown.into(),
+                                    #name,
+                                    None,
+                                    0,
+                                ))
+                            }
+                        }
+                    };
+
+                quote! {
+                    let (__tag, __rx) =
+                        <#tag as recode::RawDecoder<#item>>::raw_decode(#rest)
+                            .map_err(|e| {
+                                recode::error::add_context::<Self::Error>(
+                                    e.into(),
+                                    #name,
+                                    None,
+                                    0,
+                                )
+                            })?;
+
+                    #rest = &#rest[__rx..];
+
+                    #( #arms )*
+
+                    #fallback
+                }
+            }
+        };
+
+        let magic = magic.as_ref().map(|magic| {
+            let name = ident.unraw().to_string();
+
+            quote! {
+                recode::util::BufExt::expect_magic(&mut #rest, #magic)
+                    .map_err(|e| {
+                        recode::error::add_context::<Self::Error>(
+                            e.into(),
+                            #name,
+                            None,
+                            0,
+                        )
+                    })?;
+            }
+        });
+        let to_body = |lt: &syn::Lifetime| {
+            quote! {
+                use recode::bytes::Buf as _;
+
+                let __len = #buffer_name.len();
+                let mut #rest: &#lt [u8] = #buffer_name;
+
+                #magic
+
+                #body
+            }
+        };
+        let (imp, ty, wher) = generics.split_for_impl();
+
+        tokens.extend(match mode {
+            | Mode::Owned => {
+                let lt =
+                    syn::Lifetime::new("'__a", proc_macro2::Span::call_site());
+                let body = to_body(&lt);
+
+                quote! {
+                    impl #imp recode::RawDecoder<#output_type>
+                        for #ident #ty #wher
+                    {
+                        type Error = #error;
+
+                        #[allow(unused_variables, unused_mut)]
+                        fn raw_decode<#lt>(
+                            #buffer_name: &#lt [u8],
+                        ) -> Result<(#output_type, usize), Self::Error>
+                        where
+                            #output_type: #lt,
+                        {
+                            #body
+                        }
+                    }
+                }
+            }
+            | Mode::Borrowed(lt) => {
+                let body = to_body(lt);
+
+                quote! {
+                    impl #imp recode::BorrowDecoder<#lt, #output_type>
+                        for #ident #ty #wher
+                    {
+                        type Error = #error;
+
+                        #[allow(unused_variables, unused_mut)]
+                        fn borrow_decode(
+                            #buffer_name: &#lt [u8],
+                        ) -> Result<(#output_type, usize), Self::Error>
+                        {
+                            #body
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Generates the branch that decodes a variant, if the tag matches its id.
+fn to_raw_decode_arm(
+    variant: &DecoderVariant,
+    output_type: &syn::Type,
+    tag_item: &syn::Type,
+    rest: &syn::Ident,
+    enum_name: &str,
+    mode: Mode,
+) -> TokenStream {
+    use quote::quote;
+
+    let DecoderVariant {
+        ident, fields, id, ..
+    } = variant;
+
+    let ty_name = format!("{}::{}", enum_name, ident.unraw());
+    let stmts = fields.iter().enumerate().map(|(i, f)| {
+        let (_, binding) = field_binding(f.ident.as_ref(), i);
+        let name = field_name(f.ident.as_ref(), i);
+
+        to_raw_decode_stmt(f, &binding, rest, Some(&ty_name), &name, mode)
+    });
+    let constructor = to_constructor(fields, quote!(#output_type::#ident));
+
+    quote! {
+        if __tag == (#id) as #tag_item {
+            #( #stmts )*
+
+            return Ok((#constructor, __len - #rest.len()));
+        }
+    }
+}
+
+/// Generates the statements that decode the field `name` from the slice `rest`
+/// into `binding`, along with the fillers around it, and advance `rest` past
+/// them.
+///
+/// If `ty_name` (the name of the type) is given, errors are annotated with the
+/// location of the field.
+fn to_raw_decode_stmt(
+    field: &DecoderField,
+    binding: &syn::Ident,
+    rest: &syn::Ident,
+    ty_name: Option<&str>,
+    name: &str,
+    mode: Mode,
+) -> TokenStream {
+    let context = ty_name.map(|ty| (ty, name));
+    let before = to_raw_filler_stmts(&field.before, rest, context, mode);
+    let after = to_raw_filler_stmts(&field.after, rest, context, mode);
+    let decode = to_raw_value_stmt(field, binding, rest, ty_name, name, mode);
+
+    quote::quote! {
+        #before
+        #decode
+        #after
+    }
+}
+
+/// Generates the statements that decode and check `filler` from the slice
+/// `rest`, and advance `rest` past it, with errors annotated with `context`
+/// (the name of the type, and of the field).
+fn to_raw_filler_stmts(
+    filler: &Filler,
+    rest: &syn::Ident,
+    context: Option<(&str, &str)>,
+    mode: Mode,
+) -> TokenStream {
+    use quote::quote;
+
+    let (offset, map_err) = context_tokens(rest, context);
+
+    filler
+        .0
+        .iter()
+        .map(|item| {
+            let check = match item {
+                | FillerItem::Magic(magic) => quote! {
+                    recode::util::BufExt::expect_magic(&mut #rest, #magic)
+                        #map_err ?;
+                },
+                | FillerItem::Constant(constant) => {
+                    let FillerConstant { ty, value, with } = &**constant;
+
+                    let with = with.as_ref().unwrap_or(ty);
+                    let decode = mode.decode_call(with, ty, &quote!(#rest));
+
+                    quote! {
+                        let (__constant, __rx) = #decode #map_err ?;
+                        let __constant: #ty = __constant;
+                        let __expected: #ty = #value;
+
+                        #rest = &#rest[__rx..];
+
+                        if __constant != __expected {
+                            Err::<(), recode::Error>(
+                                recode::Error::invalid_constant(
+                                    &__expected,
+                                    &__constant,
+                                ),
+                            )
+                            #map_err ?;
+                        }
+                    }
+                }
+                | FillerItem::Reserved(bytes) => quote! {
+                    recode::util::BufExt::skip_reserved(&mut #rest, #bytes)
+                        #map_err ?;
+                },
+            };
+
+            quote! {
+                #offset
+                #check
+            }
+        })
+        .collect()
+}
+
+/// Generates the statements that decode the field `name` from the slice `rest`
+/// into `binding`, and advance `rest` past it.
+fn to_raw_value_stmt(
+    field: &DecoderField,
+    binding: &syn::Ident,
+    rest: &syn::Ident,
+    ty_name: Option<&str>,
+    name: &str,
+    mode: Mode,
+) -> TokenStream {
+    use quote::quote;
+
+    let DecoderField {
+        ident: _,
+        ty,
+        before: _,
+        after: _,
+        bits,
+        via: _,
+        try_from: _,
+        decoder:
+            DecoderFieldOpts {
+                skip,
+                skip_if,
+                map,
+                with,
+                validate,
+                len,
+                count,
+            },
+    } = field;
+
+    let unsupported = [
+        bits.map(|_| "bits"),
+        validate.as_ref().map(|_| "validate"),
+        count.as_ref().map(|_| "count"),
+    ];
+
+    if let Some(attr) = unsupported.into_iter().flatten().next() {
+        return darling::Error::custom(format!(
+            "`{}` is not supported by `RawDecoder`",
+            attr
+        ))
+        .with_span(ty)
+        .write_errors();
+    }
+
+    if skip.is_present() {
+        return quote!(let #binding = Default::default(););
+    }
+
+    let conversion = match field.conversion() {
+        | Ok(conversion) => conversion,
+        | Err(e) => return e.write_errors(),
+    };
+    let (offset, map_err) = context_tokens(rest, ty_name.map(|ty| (ty, name)));
+
+    let wire = field.wire_type();
+    let with = with.as_ref().unwrap_or(wire);
+    let decode = match len {
+        | Some(len) => {
+            let decode =
+                mode.decode_call(with, wire, &quote!(&#rest[..__field_len]));
+
+            quote! {
+                {
+                    let __field_len: usize = #len;
+
+                    recode::util::BufExt::require_n(&#rest, __field_len)
+                        #map_err ?;
+
+                    let (#binding, __rx) = #decode #map_err ?;
+
+                    if __rx != __field_len {
+                        Err::<(), recode::Error>(
+                            recode::Error::TrailingBytes(__field_len - __rx),
+                        )
+                        #map_err ?;
+                    }
+
+                    (#binding, __field_len)
+                }
+            }
+        }
+        | None => {
+            let decode = mode.decode_call(with, wire, &quote!(#rest));
+
+            quote!(#decode #map_err ?)
+        }
+    };
+    let convert = conversion.map(|conversion| {
+        let convert = conversion.to_field(ty, &quote!(#binding), name);
+
+        quote!(let #binding = #convert #map_err ?;)
+    });
+    let map = map.as_ref().map(|m| quote!(let #binding = (#m)(#binding);));
+    let stmts = quote! {
+        #offset
+
+        let (#binding, __rx) = #decode;
+
+        #rest = &#rest[__rx..];
+
+        #convert
+        #map
+    };
+
+    match skip_if {
+        | Some(skip_if) => quote! {
+            let #binding = if (#skip_if) {
+                Default::default()
+            } else {
+                #stmts
+
+                #binding
+            };
+        },
+        | None => stmts,
+    }
+}
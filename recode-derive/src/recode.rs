@@ -1,16 +1,29 @@
 #![allow(dead_code)]
 
-use darling::util::Flag;
+use darling::{ast::Data, util::Flag};
 
-use crate::{decoder, encoder};
+use crate::{
+    decoder,
+    encoder,
+    util::{BitOrder, Endian, Filler},
+};
 
 #[derive(Debug, darling::FromDeriveInput)]
-#[darling(forward_attrs(allow, doc, cfg))]
-#[darling(attributes(recode), supports(struct_named, struct_unit))]
+#[darling(forward_attrs(allow, doc, cfg, repr))]
+#[darling(attributes(recode), supports(struct_any, enum_any))]
 pub(crate) struct Recode {
     ident: syn::Ident,
     generics: syn::Generics,
-    data: darling::ast::Data<(), RecodeField>,
+    attrs: Vec<syn::Attribute>,
+    data: Data<RecodeVariant, RecodeField>,
+    tag: Option<syn::Type>,
+    repr: Option<syn::Type>,
+    transparent: Flag,
+    endian: Option<Endian>,
+    ctx: Option<syn::Type>,
+    magic: Option<syn::LitByteStr>,
+    bits: Option<u32>,
+    bit_order: Option<BitOrder>,
     error: Option<syn::Type>,
     buffer_name: Option<syn::Ident>,
     #[darling(default)]
@@ -19,27 +32,77 @@ pub(crate) struct Recode {
     encoder: encoder::EncoderOpts,
 }
 
+#[derive(Debug, Clone, darling::FromVariant)]
+#[darling(attributes(recode))]
+struct RecodeVariant {
+    ident: syn::Ident,
+    fields: darling::ast::Fields<RecodeField>,
+    id: Option<syn::Expr>,
+    discriminant: Option<syn::Expr>,
+    other: Flag,
+}
+
+/// The options of a field, which are shared by its decoder and encoder.
+///
+/// Options set under `decoder(...)` or `encoder(...)` take precedence over
+/// the shared ones, and `skip` applies if it is set on either.
 #[derive(Debug, Clone, darling::FromField)]
 #[darling(attributes(recode))]
 struct RecodeField {
     ident: Option<syn::Ident>,
     ty: syn::Type,
+    #[darling(default)]
+    before: Filler,
+    #[darling(default)]
+    after: Filler,
+    bits: Option<u32>,
+    via: Option<syn::Type>,
+    try_from: Option<syn::Type>,
     skip: Flag,
     skip_if: Option<syn::Expr>,
     with: Option<syn::Type>,
     validate: Option<syn::Expr>,
     #[darling(default)]
+    map: MapOpts,
+    size: Option<syn::Expr>,
+    #[darling(default)]
     decoder: decoder::DecoderFieldOpts,
     #[darling(default)]
     encoder: encoder::EncoderFieldOpts,
 }
 
+/// The mappings applied to a field after it is decoded, and before it is
+/// encoded.
+#[derive(Debug, Clone, Default, darling::FromMeta)]
+#[darling(default)]
+struct MapOpts {
+    decode: Option<syn::Expr>,
+    encode: Option<syn::Expr>,
+}
+
 impl darling::ToTokens for Recode {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        self.to_decoder().to_tokens(tokens);
+        self.to_encoder().to_tokens(tokens);
+    }
+}
+
+impl Recode {
+    /// Returns the decoder half of the input.
+    pub(crate) fn to_decoder(&self) -> decoder::Decoder {
         decoder::Decoder {
             ident: self.ident.clone(),
             generics: self.generics.clone(),
+            attrs: self.attrs.clone(),
             data: self.get_decoder_data(),
+            tag: self.tag.clone(),
+            repr: self.repr.clone(),
+            transparent: self.transparent,
+            endian: self.endian,
+            ctx: self.ctx.clone(),
+            magic: self.magic.clone(),
+            bits: self.bits,
+            bit_order: self.bit_order,
             decoder: decoder::DecoderOpts {
                 error: self.decoder.error.clone().or(self.error.clone()),
                 buffer_name: self
@@ -50,12 +113,23 @@ impl darling::ToTokens for Recode {
                 ..self.decoder.clone()
             },
         }
-        .to_tokens(tokens);
+    }
 
+    /// Returns the encoder half of the input.
+    pub(crate) fn to_encoder(&self) -> encoder::Encoder {
         encoder::Encoder {
             ident: self.ident.clone(),
             generics: self.generics.clone(),
+            attrs: self.attrs.clone(),
             data: self.get_encoder_data(),
+            tag: self.tag.clone(),
+            repr: self.repr.clone(),
+            transparent: self.transparent,
+            endian: self.endian,
+            ctx: self.ctx.clone(),
+            magic: self.magic.clone(),
+            bits: self.bits,
+            bit_order: self.bit_order,
             encoder: encoder::EncoderOpts {
                 error: self.encoder.error.clone().or(self.error.clone()),
                 buffer_name: self
@@ -66,54 +140,103 @@ impl darling::ToTokens for Recode {
                 ..self.encoder.clone()
             },
         }
-        .to_tokens(tokens);
     }
-}
 
-impl Recode {
     #[inline(always)]
     fn get_decoder_data(
         &self,
-    ) -> darling::ast::Data<(), decoder::DecoderField> {
-        self.data
-            .clone()
-            .map_struct_fields(|f| decoder::DecoderField {
-                ident: f.ident,
-                ty: f.ty,
-                decoder: decoder::DecoderFieldOpts {
-                    skip: if f.skip.is_present() {
-                        Flag::present()
-                    } else {
-                        f.encoder.skip
-                    },
-                    skip_if: f.skip_if.or(f.encoder.skip_if),
-                    with: f.decoder.with.or(f.with),
-                    validate: f.decoder.validate.or(f.validate),
-                    ..f.decoder
-                },
-            })
+    ) -> Data<decoder::DecoderVariant, decoder::DecoderField> {
+        match self.data.clone() {
+            | Data::Enum(variants) => Data::Enum(
+                variants
+                    .into_iter()
+                    .map(|v| decoder::DecoderVariant {
+                        ident: v.ident,
+                        fields: v.fields.map(RecodeField::into_decoder_field),
+                        id: v.id,
+                        discriminant: v.discriminant,
+                        other: v.other,
+                    })
+                    .collect(),
+            ),
+            | Data::Struct(fields) => {
+                Data::Struct(fields.map(RecodeField::into_decoder_field))
+            }
+        }
     }
 
     #[inline(always)]
     fn get_encoder_data(
         &self,
-    ) -> darling::ast::Data<(), encoder::EncoderField> {
-        self.data
-            .clone()
-            .map_struct_fields(|f| encoder::EncoderField {
-                ident: f.ident,
-                ty: f.ty,
-                encoder: encoder::EncoderFieldOpts {
-                    skip: if f.skip.is_present() {
-                        Flag::present()
-                    } else {
-                        f.encoder.skip
-                    },
-                    skip_if: f.skip_if.or(f.encoder.skip_if),
-                    with: f.encoder.with.or(f.with),
-                    validate: f.encoder.validate.or(f.validate),
-                    ..f.encoder
-                },
-            })
+    ) -> Data<encoder::EncoderVariant, encoder::EncoderField> {
+        match self.data.clone() {
+            | Data::Enum(variants) => Data::Enum(
+                variants
+                    .into_iter()
+                    .map(|v| encoder::EncoderVariant {
+                        ident: v.ident,
+                        fields: v.fields.map(RecodeField::into_encoder_field),
+                        id: v.id,
+                        discriminant: v.discriminant,
+                        other: v.other,
+                    })
+                    .collect(),
+            ),
+            | Data::Struct(fields) => {
+                Data::Struct(fields.map(RecodeField::into_encoder_field))
+            }
+        }
+    }
+}
+
+impl RecodeField {
+    fn into_decoder_field(self) -> decoder::DecoderField {
+        decoder::DecoderField {
+            ident: self.ident,
+            ty: self.ty,
+            before: self.before,
+            after: self.after,
+            bits: self.bits,
+            via: self.via,
+            try_from: self.try_from,
+            decoder: decoder::DecoderFieldOpts {
+                skip: merge_flags(self.decoder.skip, self.skip),
+                skip_if: self.decoder.skip_if.or(self.skip_if),
+                map: self.decoder.map.or(self.map.decode),
+                with: self.decoder.with.or(self.with),
+                validate: self.decoder.validate.or(self.validate),
+                ..self.decoder
+            },
+        }
+    }
+
+    fn into_encoder_field(self) -> encoder::EncoderField {
+        encoder::EncoderField {
+            ident: self.ident,
+            ty: self.ty,
+            before: self.before,
+            after: self.after,
+            bits: self.bits,
+            via: self.via,
+            try_from: self.try_from,
+            encoder: encoder::EncoderFieldOpts {
+                skip: merge_flags(self.encoder.skip, self.skip),
+                skip_if: self.encoder.skip_if.or(self.skip_if),
+                map_encode: self.map.encode,
+                with: self.encoder.with.or(self.with),
+                size: self.encoder.size.or(self.size),
+                validate: self.encoder.validate.or(self.validate),
+                ..self.encoder
+            },
+        }
+    }
+}
+
+/// Returns a flag that is present if either `specific` or `shared` is.
+fn merge_flags(specific: Flag, shared: Flag) -> Flag {
+    if shared.is_present() {
+        shared
+    } else {
+        specific
     }
 }
@@ -1,3 +1,4 @@
+use darling::FromMeta;
 use syn::parse::{Parse, Parser};
 
 fn str_to_type(ty: &str) -> syn::Type {
@@ -13,3 +14,754 @@ pub(crate) fn default_buffer_name() -> syn::Ident {
 pub(crate) fn box_type() -> syn::Type {
     str_to_type("Box<dyn std::error::Error>")
 }
+
+/// Returns the member used to access a field, along with the name of the local
+/// variable the field is bound to in generated code.
+///
+/// Named fields are bound to their own names, while tuple fields are bound to
+/// `__field_<index>`.
+pub(crate) fn field_binding(
+    ident: Option<&syn::Ident>,
+    index: usize,
+) -> (syn::Member, syn::Ident) {
+    match ident {
+        | Some(ident) => (syn::Member::Named(ident.clone()), ident.clone()),
+        | None => (
+            syn::Member::Unnamed(index.into()),
+            quote::format_ident!("__field_{}", index),
+        ),
+    }
+}
+
+/// Returns the name of a field in errors: its own name, or its index for
+/// tuple fields.
+pub(crate) fn field_name(ident: Option<&syn::Ident>, index: usize) -> String {
+    use syn::ext::IdentExt;
+
+    match ident {
+        | Some(ident) => ident.unraw().to_string(),
+        | None => index.to_string(),
+    }
+}
+
+/// Returns the element type of the collection type `ty` (the first type
+/// argument of e.g. `Vec<T>`), or `None` if `ty` has no type arguments.
+pub(crate) fn element_type(ty: &syn::Type) -> Option<syn::Type> {
+    let syn::Type::Path(syn::TypePath {
+        qself: None, path, ..
+    }) = ty
+    else {
+        return None;
+    };
+
+    let syn::PathArguments::AngleBracketed(ref args) =
+        path.segments.last()?.arguments
+    else {
+        return None;
+    };
+
+    args.args.iter().find_map(|arg| match arg {
+        | syn::GenericArgument::Type(ty) => Some(ty.clone()),
+        | _ => None,
+    })
+}
+
+/// Bytes before or after a field that have no struct member of their own:
+/// magic bytes, constants and reserved bytes, in the order they are declared
+/// (e.g. `before(magic = b"\x89PNG", reserved = 2)`).
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Filler(pub(crate) Vec<FillerItem>);
+
+#[derive(Clone, Debug)]
+pub(crate) enum FillerItem {
+    /// Bytes that must be equal to the given ones.
+    Magic(syn::LitByteStr),
+    /// A value that must be equal to a constant.
+    Constant(Box<FillerConstant>),
+    /// The given number of bytes, which must be zero.
+    Reserved(usize),
+}
+
+/// A `constant(ty = "u16", value = 1)` filler, optionally encoded `with` a
+/// codec other than `ty`.
+#[derive(Clone, Debug, darling::FromMeta)]
+pub(crate) struct FillerConstant {
+    pub(crate) ty: syn::Type,
+    pub(crate) value: syn::Expr,
+    pub(crate) with: Option<syn::Type>,
+}
+
+impl darling::FromMeta for Filler {
+    fn from_list(items: &[darling::ast::NestedMeta]) -> darling::Result<Self> {
+        use darling::ast::NestedMeta;
+        use quote::ToTokens;
+
+        let mut errors = darling::Error::accumulator();
+        let fillers = items
+            .iter()
+            .filter_map(|item| {
+                let NestedMeta::Meta(meta) = item else {
+                    errors.push(darling::Error::unsupported_format("literal"));
+
+                    return None;
+                };
+                let filler = match meta.path().get_ident() {
+                    | Some(i) if i == "magic" => {
+                        FromMeta::from_meta(meta).map(FillerItem::Magic)
+                    }
+                    | Some(i) if i == "constant" => {
+                        FromMeta::from_meta(meta).map(FillerItem::Constant)
+                    }
+                    | Some(i) if i == "reserved" => {
+                        FromMeta::from_meta(meta).map(FillerItem::Reserved)
+                    }
+                    | _ => Err(darling::Error::unknown_field_with_alts(
+                        &meta.path().to_token_stream().to_string(),
+                        &["magic", "constant", "reserved"],
+                    )),
+                };
+
+                errors.handle(filler.map_err(|e| e.with_span(meta)))
+            })
+            .collect();
+
+        errors.finish_with(Filler(fillers))
+    }
+}
+
+impl Filler {
+    /// Returns whether there are no filler bytes.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Makes constants without an explicit codec use the byte order `endian`.
+    pub(crate) fn set_endian(&mut self, endian: Endian) {
+        for item in &mut self.0 {
+            if let FillerItem::Constant(c) = item {
+                if c.with.is_none() {
+                    c.with = endian.codec_for(&c.ty);
+                }
+            }
+        }
+    }
+
+    /// Returns the bounds of the number of bytes of the fillers, for the
+    /// codec trait `codec`.
+    pub(crate) fn size_bounds(
+        &self,
+        codec: &proc_macro2::TokenStream,
+    ) -> SizeBounds {
+        SizeBounds::sum(self.0.iter().map(|item| match item {
+            | FillerItem::Magic(magic) => {
+                SizeBounds::exact(magic.value().len())
+            }
+            | FillerItem::Constant(constant) => {
+                let FillerConstant { ty, with, .. } = &**constant;
+
+                SizeBounds::of(codec, with.as_ref().unwrap_or(ty), ty)
+            }
+            | FillerItem::Reserved(bytes) => SizeBounds::exact(*bytes),
+        }))
+    }
+}
+
+/// The byte order used for numeric fields that have no explicit codec.
+#[derive(Clone, Copy, Debug, darling::FromMeta)]
+pub(crate) enum Endian {
+    #[darling(rename = "big")]
+    Big,
+    #[darling(rename = "little")]
+    Little,
+}
+
+impl Endian {
+    /// Returns the codec used for a field of type `ty`, or `None` if `ty` is
+    /// not a numeric type.
+    pub(crate) fn codec_for(self, ty: &syn::Type) -> Option<syn::Type> {
+        const NUMBERS: &[&str] = &[
+            "i8", "u8", "i16", "u16", "i24", "u24", "i32", "u32", "i40", "u40",
+            "i48", "u48", "i56", "u56", "i64", "u64", "i128", "u128", "f16",
+            "bf16", "f32", "f64", "char",
+        ];
+
+        let syn::Type::Path(syn::TypePath {
+            qself: None, path, ..
+        }) = ty
+        else {
+            return None;
+        };
+
+        let segment = path.segments.last()?;
+
+        if !segment.arguments.is_empty()
+            || !NUMBERS.iter().any(|i| segment.ident == i)
+        {
+            return None;
+        }
+
+        Some(self.wrap(ty))
+    }
+
+    /// Returns the codec for the numeric type `ty` in this byte order.
+    pub(crate) fn wrap(self, ty: &syn::Type) -> syn::Type {
+        match self {
+            | Endian::Big => syn::parse_quote!(recode::codec::BigEndian<#ty>),
+            | Endian::Little => {
+                syn::parse_quote!(recode::codec::LittleEndian<#ty>)
+            }
+        }
+    }
+}
+
+/// Generates a call that decodes a `ty` from `buf` with the codec `with`.
+///
+/// If the container has a context type, the codec is called through
+/// `DecodeWith`, passing the `__ctx` argument along (which a field named `ctx`
+/// cannot shadow).
+pub(crate) fn decode_call(
+    with: &syn::Type,
+    ty: &syn::Type,
+    buf: &syn::Ident,
+    ctx: Option<&syn::Type>,
+) -> proc_macro2::TokenStream {
+    match ctx {
+        | Some(ctx) => quote::quote! {
+            <#with as recode::DecodeWith<#ctx, #ty>>::decode_with(#buf, __ctx)
+        },
+        | None => quote::quote! {
+            <#with as recode::Decoder<#ty>>::decode(#buf)
+        },
+    }
+}
+
+/// Generates a call that encodes `input` (a `&ty`) into `buf` with the codec
+/// `with`.
+///
+/// If the container has a context type, the codec is called through
+/// `EncodeWith`, passing the `__ctx` argument along.
+pub(crate) fn encode_call(
+    with: &syn::Type,
+    ty: &syn::Type,
+    input: &proc_macro2::TokenStream,
+    buf: &syn::Ident,
+    ctx: Option<&syn::Type>,
+) -> proc_macro2::TokenStream {
+    match ctx {
+        | Some(ctx) => quote::quote! {
+            <#with as recode::EncodeWith<#ctx, #ty>>::encode_with(
+                #input, #buf, __ctx,
+            )
+        },
+        | None => quote::quote! {
+            <#with as recode::Encoder<#ty>>::encode(#input, #buf)
+        },
+    }
+}
+
+/// Generates a call that returns the encoded size of `input` (a `&ty`) with
+/// the codec `with`.
+pub(crate) fn size_of_call(
+    with: &syn::Type,
+    ty: &syn::Type,
+    input: &proc_macro2::TokenStream,
+    ctx: Option<&syn::Type>,
+) -> proc_macro2::TokenStream {
+    match ctx {
+        | Some(ctx) => quote::quote! {
+            <#with as recode::EncodeWith<#ctx, #ty>>::size_of_with(#input, __ctx)
+        },
+        | None => quote::quote! {
+            <#with as recode::Encoder<#ty>>::size_of(#input)
+        },
+    }
+}
+
+/// The order in which consecutive bit fields are packed.
+#[derive(Clone, Copy, Debug, Default, darling::FromMeta)]
+pub(crate) enum BitOrder {
+    #[default]
+    #[darling(rename = "msb")]
+    Msb,
+    #[darling(rename = "lsb")]
+    Lsb,
+}
+
+impl quote::ToTokens for BitOrder {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        tokens.extend(match self {
+            | BitOrder::Msb => quote::quote!(recode::bits::BitOrder::Msb),
+            | BitOrder::Lsb => quote::quote!(recode::bits::BitOrder::Lsb),
+        });
+    }
+}
+
+/// How the bit fields of a container are packed.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Packing {
+    /// The width of the backing integer of a `bits = N` container, which
+    /// packs all of its fields.
+    pub(crate) bits: Option<u32>,
+    pub(crate) order: BitOrder,
+    pub(crate) endian: Option<Endian>,
+}
+
+impl Packing {
+    /// Returns the integer type backing a run of bit fields, along with the
+    /// codec it is decoded/encoded with.
+    ///
+    /// Odd widths are backed by the `ux` types, which are resolved through
+    /// `recode::__bits_backing!` so that a missing `ux` feature is reported
+    /// as such.
+    pub(crate) fn backing(self, width: u32) -> (syn::Type, syn::Type) {
+        let ty: syn::Type = match width {
+            | 8 => syn::parse_quote!(u8),
+            | 16 => syn::parse_quote!(u16),
+            | 32 => syn::parse_quote!(u32),
+            | 24 | 40 | 48 | 56 => {
+                let ident = quote::format_ident!("u{}", width);
+
+                syn::parse_quote!(recode::__bits_backing!(#ident))
+            }
+            | _ => syn::parse_quote!(u64),
+        };
+        let codec = self.endian.map_or_else(|| ty.clone(), |e| e.wrap(&ty));
+
+        (ty, codec)
+    }
+}
+
+/// Checks that the bit fields of a run have no `fillers`, which can only
+/// precede or follow fields of whole bytes.
+pub(crate) fn check_bits_fillers<'a>(
+    fillers: impl IntoIterator<Item = &'a Filler>,
+) -> darling::Result<()> {
+    match fillers.into_iter().all(Filler::is_empty) {
+        | true => Ok(()),
+        | false => Err(darling::Error::custom(
+            "`before` and `after` cannot be used on bit fields",
+        )),
+    }
+}
+
+/// How a field is laid out, for grouping bit fields into runs.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum FieldLayout {
+    /// The field is not decoded/encoded at all.
+    Skipped,
+    /// The field takes whole bytes.
+    Bytes,
+    /// The field is packed into the given number of bits.
+    Bits(u32),
+}
+
+/// A sequence of fields that is decoded/encoded as a unit.
+#[derive(Clone, Debug)]
+pub(crate) enum Run {
+    /// A single field, at the given index.
+    Field(usize),
+    /// Consecutive bit fields, packed into a `width` bits wide integer.
+    Bits {
+        fields: Vec<(usize, u32)>,
+        width: u32,
+    },
+}
+
+/// Groups consecutive bit fields into runs.
+///
+/// Each run of bit fields must add up to a whole number of bytes, of at most
+/// 64 bits. If the container has a `bits` width, all of its (non-skipped)
+/// fields must be bit fields that add up to exactly that width.
+pub(crate) fn to_runs(
+    layouts: &[FieldLayout],
+    packing: Packing,
+) -> Result<Vec<Run>, String> {
+    fn end_run(
+        current: &mut Vec<(usize, u32)>,
+        width: &mut u32,
+        runs: &mut Vec<Run>,
+    ) -> Result<(), String> {
+        if current.is_empty() {
+            return Ok(());
+        }
+
+        if !width.is_multiple_of(8) || *width > 64 {
+            return Err(format!(
+                "bit fields must add up to a whole number of bytes, of at \
+                 most 64 bits (found {} bits)",
+                width
+            ));
+        }
+
+        runs.push(Run::Bits {
+            fields: std::mem::take(current),
+            width: std::mem::take(width),
+        });
+
+        Ok(())
+    }
+
+    let mut runs = Vec::new();
+    let mut current = Vec::new();
+    let mut width = 0;
+
+    for (i, layout) in layouts.iter().enumerate() {
+        match *layout {
+            | FieldLayout::Skipped => runs.push(Run::Field(i)),
+            | FieldLayout::Bytes if packing.bits.is_some() => {
+                return Err("all fields of a `bits` container must have a \
+                            `bits` width"
+                    .into());
+            }
+            | FieldLayout::Bytes => {
+                end_run(&mut current, &mut width, &mut runs)?;
+                runs.push(Run::Field(i));
+            }
+            | FieldLayout::Bits(0) => {
+                return Err("bit fields must be at least 1 bit wide".into());
+            }
+            | FieldLayout::Bits(bits) => {
+                current.push((i, bits));
+                width += bits;
+            }
+        }
+    }
+
+    if let Some(bits) = packing.bits {
+        if bits != width {
+            return Err(format!(
+                "bit fields add up to {} bits, but the container is {} bits \
+                 wide",
+                width, bits
+            ));
+        }
+    }
+
+    end_run(&mut current, &mut width, &mut runs)?;
+
+    Ok(runs)
+}
+
+/// The bounds of the number of bytes a value is decoded from, or encoded into,
+/// as constant expressions for `MIN_SIZE` and `MAX_SIZE`.
+#[derive(Clone, Debug)]
+pub(crate) struct SizeBounds {
+    pub(crate) min: proc_macro2::TokenStream,
+    pub(crate) max: proc_macro2::TokenStream,
+}
+
+impl SizeBounds {
+    /// Bounds of a value that is always `len` bytes long.
+    pub(crate) fn exact(len: usize) -> Self {
+        Self {
+            min: quote::quote!(#len),
+            max: quote::quote!(Some(#len)),
+        }
+    }
+
+    /// Bounds of a value whose length is not known at compile time.
+    pub(crate) fn unbounded() -> Self {
+        Self {
+            min: quote::quote!(0),
+            max: quote::quote!(None),
+        }
+    }
+
+    /// Bounds of a value handled by the codec `with`, where `codec` is the
+    /// codec trait (`recode::Decoder` or `recode::Encoder`).
+    pub(crate) fn of(
+        codec: &proc_macro2::TokenStream,
+        with: &syn::Type,
+        ty: &syn::Type,
+    ) -> Self {
+        Self {
+            min: quote::quote!(<#with as #codec<#ty>>::MIN_SIZE),
+            max: quote::quote!(<#with as #codec<#ty>>::MAX_SIZE),
+        }
+    }
+
+    /// Bounds of a value that may be left out.
+    pub(crate) fn optional(self) -> Self {
+        Self {
+            min: quote::quote!(0),
+            max: self.max,
+        }
+    }
+
+    /// Bounds of consecutive values.
+    pub(crate) fn sum(bounds: impl IntoIterator<Item = Self>) -> Self {
+        let (mins, maxs): (Vec<_>, Vec<_>) =
+            bounds.into_iter().map(|b| (b.min, b.max)).unzip();
+
+        Self {
+            min: quote::quote!(0 #( + #mins )*),
+            max: quote::quote! {
+                recode::util::size::sum_max(&[#( #maxs ),*])
+            },
+        }
+    }
+
+    /// Bounds of a value that is one of several alternatives.
+    pub(crate) fn any(bounds: impl IntoIterator<Item = Self>) -> Self {
+        let (mins, maxs): (Vec<_>, Vec<_>) =
+            bounds.into_iter().map(|b| (b.min, b.max)).unzip();
+
+        Self {
+            min: quote::quote!(recode::util::size::min_of(&[#( #mins ),*])),
+            max: quote::quote!(recode::util::size::max_of(&[#( #maxs ),*])),
+        }
+    }
+
+    /// Bounds of the runs of a container, where `field` returns the bounds of
+    /// the field at a given index.
+    pub(crate) fn of_runs(
+        runs: &[Run],
+        mut field: impl FnMut(usize) -> Self,
+    ) -> Self {
+        Self::sum(runs.iter().map(|run| match *run {
+            | Run::Field(i) => field(i),
+            | Run::Bits { width, .. } => Self::exact(width as usize / 8),
+        }))
+    }
+
+    /// Generates the `MIN_SIZE` and `MAX_SIZE` items of a codec impl.
+    pub(crate) fn to_consts(&self) -> proc_macro2::TokenStream {
+        let Self { min, max } = self;
+
+        quote::quote! {
+            const MIN_SIZE: usize = #min;
+            const MAX_SIZE: Option<usize> = #max;
+        }
+    }
+}
+
+/// How a field is converted from, and into, the type it is decoded from and
+/// encoded as.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Conversion<'a> {
+    /// Converted with `From`, from and into the `via` type.
+    Via(&'a syn::Type),
+    /// Converted with `TryFrom`, from and into the `try_from` type.
+    TryFrom(&'a syn::Type),
+}
+
+impl<'a> Conversion<'a> {
+    /// Returns the conversion of a field with the given `via` and `try_from`
+    /// types, if any.
+    pub(crate) fn new(
+        via: Option<&'a syn::Type>,
+        try_from: Option<&'a syn::Type>,
+    ) -> darling::Result<Option<Self>> {
+        match (via, try_from) {
+            | (Some(_), Some(ty)) => Err(darling::Error::custom(
+                "`via` and `try_from` cannot be used together",
+            )
+            .with_span(ty)),
+            | (Some(ty), None) => Ok(Some(Self::Via(ty))),
+            | (None, Some(ty)) => Ok(Some(Self::TryFrom(ty))),
+            | (None, None) => Ok(None),
+        }
+    }
+
+    /// Returns the type the field is decoded from and encoded as.
+    pub(crate) fn wire_type(self) -> &'a syn::Type {
+        match self {
+            | Self::Via(ty) | Self::TryFrom(ty) => ty,
+        }
+    }
+
+    /// Generates an expression that converts `value` (of the wire type) into
+    /// a `Result<ty, recode::Error>`, where `field` is the name of the field.
+    pub(crate) fn to_field(
+        self,
+        ty: &syn::Type,
+        value: &proc_macro2::TokenStream,
+        field: &str,
+    ) -> proc_macro2::TokenStream {
+        match self {
+            | Self::Via(wire) => quote::quote! {
+                Ok::<#ty, recode::Error>(<#ty as From<#wire>>::from(#value))
+            },
+            | Self::TryFrom(wire) => {
+                let error = invalid_conversion(wire, field);
+
+                quote::quote! {
+                    <#ty as TryFrom<#wire>>::try_from(#value)
+                        .map_err(|_| #error)
+                }
+            }
+        }
+    }
+
+    /// Generates an expression that converts `value` (a `&ty`) into a
+    /// `Result<wire, recode::Error>`, where `field` is the name of the field.
+    pub(crate) fn to_wire(
+        self,
+        ty: &syn::Type,
+        value: &proc_macro2::TokenStream,
+        field: &str,
+    ) -> proc_macro2::TokenStream {
+        match self {
+            | Self::Via(wire) => quote::quote! {
+                Ok::<#wire, recode::Error>(<#wire as From<#ty>>::from(
+                    Clone::clone(#value),
+                ))
+            },
+            | Self::TryFrom(wire) => {
+                let error = invalid_conversion(wire, field);
+
+                quote::quote! {
+                    <#wire as TryFrom<#ty>>::try_from(Clone::clone(#value))
+                        .map_err(|_| #error)
+                }
+            }
+        }
+    }
+}
+
+/// Generates the error of a failed conversion of `field` from/to `wire`.
+fn invalid_conversion(
+    wire: &syn::Type,
+    field: &str,
+) -> proc_macro2::TokenStream {
+    let wire = quote::quote!(#wire).to_string().replace(' ', "");
+
+    quote::quote! {
+        recode::Error::InvalidConversion {
+            field: #field,
+            ty: #wire,
+        }
+    }
+}
+
+/// Resolves the ids of the variants of an enum from their `id` attributes and
+/// discriminants.
+///
+/// Like a discriminant, the id of a variant with neither is the id of the
+/// previous variant plus one, or zero for the first variant.
+pub(crate) fn resolve_ids<'a>(
+    variants: impl IntoIterator<
+        Item = (&'a mut Option<syn::Expr>, Option<&'a syn::Expr>),
+    >,
+) {
+    let mut base: syn::Expr = syn::parse_quote!(0);
+    let mut offset = 0usize;
+
+    for (id, discriminant) in variants {
+        if let Some(explicit) = id.as_ref().or(discriminant) {
+            base = explicit.clone();
+            offset = 0;
+        }
+
+        // unsuffixed, so that the id takes the type of the base
+        let lit = proc_macro2::Literal::usize_unsuffixed(offset);
+
+        *id = Some(match offset {
+            | 0 => base.clone(),
+            | _ => syn::parse_quote!((#base) + #lit),
+        });
+        offset += 1;
+    }
+}
+
+/// Checks the `other` variants of an enum, given their names and number of
+/// fields: there can be at most one, with a single field that holds the
+/// unknown id.
+pub(crate) fn check_other(
+    others: &[(&syn::Ident, usize)],
+) -> darling::Result<()> {
+    match others {
+        | [] | [(_, 1)] => Ok(()),
+        | [(ident, _)] => Err(darling::Error::custom(
+            "the `other` variant must have exactly one field",
+        )
+        .with_span(*ident)),
+        | [_, (ident, _), ..] => Err(darling::Error::custom(
+            "an enum can only have one `other` variant",
+        )
+        .with_span(*ident)),
+    }
+}
+
+/// The primitive integer types an enum can be `#[repr(...)]`.
+const INTEGERS: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128",
+];
+
+/// Returns the `repr` type of a C-like enum if given, or else the integer type
+/// of its `#[repr(...)]` attribute.
+fn repr_type(
+    repr: Option<&syn::Type>,
+    attrs: &[syn::Attribute],
+) -> Option<syn::Type> {
+    repr.cloned().or_else(|| {
+        let mut ty = None;
+
+        for attr in attrs.iter().filter(|a| a.path().is_ident("repr")) {
+            // other representation hints (e.g. `C`) are ignored
+            let _ = attr.parse_nested_meta(|meta| {
+                if INTEGERS.iter().any(|i| meta.path.is_ident(i)) {
+                    ty = meta.path.get_ident().map(|i| syn::parse_quote!(#i));
+                }
+
+                Ok(())
+            });
+        }
+
+        ty
+    })
+}
+
+/// Returns the tag of a C-like enum: the `repr` type if given, or else the
+/// integer type of its `#[repr(...)]` attribute, encoded in `endian` order.
+pub(crate) fn repr_tag(
+    repr: Option<&syn::Type>,
+    attrs: &[syn::Attribute],
+    endian: Option<Endian>,
+) -> Option<syn::Type> {
+    let ty = repr_type(repr, attrs)?;
+
+    Some(endian.and_then(|e| e.codec_for(&ty)).unwrap_or(ty))
+}
+
+/// Returns the type that the tag of an enum is decoded into and encoded from.
+///
+/// This is the primitive integer type of a C-like enum without an explicit
+/// `tag`, so that ids are compared in the type of its discriminants (which
+/// may be negative), or else `usize`.
+pub(crate) fn tag_item(
+    tag: Option<&syn::Type>,
+    repr: Option<&syn::Type>,
+    attrs: &[syn::Attribute],
+) -> syn::Type {
+    let primitive = |ty: &syn::Type| match ty {
+        | syn::Type::Path(syn::TypePath {
+            qself: None, path, ..
+        }) => INTEGERS.iter().any(|i| path.is_ident(i)),
+        | _ => false,
+    };
+
+    match repr_type(repr, attrs) {
+        | Some(ty) if tag.is_none() && primitive(&ty) => ty,
+        | _ => syn::parse_quote!(usize),
+    }
+}
+
+/// Generates the `recode::Error::UnknownTag` error for the tag in `__tag`, of
+/// type `item`.
+///
+/// Tags that do not fit in a `usize` (e.g. negative ones) are reported as
+/// `recode::Error::IntConversion` instead.
+pub(crate) fn unknown_tag(item: &syn::Type) -> proc_macro2::TokenStream {
+    match item {
+        | syn::Type::Path(p) if p.path.is_ident("usize") => {
+            quote::quote!(recode::Error::UnknownTag(__tag))
+        }
+        | _ => quote::quote! {
+            match usize::try_from(__tag) {
+                | Ok(tag) => recode::Error::UnknownTag(tag),
+                | Err(e) => recode::Error::IntConversion(e.into()),
+            }
+        },
+    }
+}
@@ -64,6 +64,18 @@ where
                         ))?;
                     }
 
+                    // frames that cannot hold a value are rejected before
+                    // their bytes are buffered
+                    if len < F::MIN_SIZE || F::MAX_SIZE.is_some_and(|m| len > m)
+                    {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            LengthDelimitedCodecError(
+                                "frame length does not fit the frame type",
+                            ),
+                        ))?;
+                    }
+
                     src.reserve(len);
                     self.state = DecodeState::Data(len);
                 }
@@ -110,7 +122,11 @@ where
         item: F,
         dst: &mut BytesMut,
     ) -> Result<(), Self::Error> {
-        let len = item.size();
+        // the size of fixed size frames is known without walking them
+        let len = match F::MAX_SIZE {
+            | Some(max) if max == F::MIN_SIZE => max,
+            | _ => item.size(),
+        };
 
         dst.reserve(len);
 
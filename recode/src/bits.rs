@@ -0,0 +1,386 @@
+//! Support for structs that pack several fields into the bits of a single
+//! integer.
+//!
+//! The derive macros use [`BitReader`] and [`BitWriter`] to unpack and pack
+//! the fields of `#[recode(bits = N)]` structs, or runs of
+//! `#[recode(bits = N)]` fields. Field types convert from/to their bits with
+//! [`FromBits`] and [`ToBits`].
+
+use crate::Error;
+
+/// The order in which consecutive bit fields are packed into their backing
+/// integer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BitOrder {
+    /// The first field occupies the most significant bits.
+    #[default]
+    Msb,
+    /// The first field occupies the least significant bits.
+    Lsb,
+}
+
+/// A type that can be unpacked from a bit field.
+pub trait FromBits: Sized {
+    /// Converts the bits of a field into a value.
+    fn from_bits(bits: u64) -> Result<Self, Error>;
+}
+
+/// A type that can be packed into a bit field.
+pub trait ToBits {
+    /// Converts a value into the bits of a field.
+    fn to_bits(&self) -> u64;
+}
+
+macro_rules! impl_bits {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl FromBits for $t {
+                #[inline]
+                fn from_bits(bits: u64) -> Result<Self, Error> {
+                    Self::try_from(bits)
+                        .map_err(crate::codec::TryFromIntError::from)
+                        .map_err(Into::into)
+                }
+            }
+
+            impl ToBits for $t {
+                #[inline]
+                fn to_bits(&self) -> u64 {
+                    u64::from(*self)
+                }
+            }
+        )+
+    };
+}
+
+impl_bits!(u8, u16, u32, u64);
+
+impl FromBits for bool {
+    #[inline]
+    fn from_bits(bits: u64) -> Result<Self, Error> {
+        match bits {
+            | 0 => Ok(false),
+            | 1 => Ok(true),
+            | _ => Err(Error::InvalidBool(bits as u8)),
+        }
+    }
+}
+
+impl ToBits for bool {
+    #[inline]
+    fn to_bits(&self) -> u64 {
+        u64::from(*self)
+    }
+}
+
+/// Resolves the `ux` integer backing a run of bit fields that is 24, 40, 48
+/// or 56 bits wide, for the derive macros.
+#[cfg(feature = "ux")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bits_backing {
+    ($t:ident) => {
+        $crate::codec::$t
+    };
+}
+
+#[cfg(not(feature = "ux"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bits_backing {
+    ($t:ident) => {
+        ::core::compile_error!(::core::concat!(
+            "bit fields packed into a `",
+            ::core::stringify!($t),
+            "` require the `ux` feature of `recode`",
+        ))
+    };
+}
+
+/// Returns the mask of the `bits` low bits of an integer.
+#[inline]
+fn mask(bits: u32) -> u64 {
+    u64::MAX.checked_shr(64 - bits).unwrap_or(0)
+}
+
+/// Unpacks consecutive bit fields from a backing integer that is `width` bits
+/// wide.
+#[derive(Debug, Clone)]
+pub struct BitReader {
+    value: u64,
+    width: u32,
+    order: BitOrder,
+    pos: u32,
+}
+
+impl BitReader {
+    /// Creates a reader over the bits of `value`.
+    pub fn new(value: impl Into<u64>, width: u32, order: BitOrder) -> Self {
+        Self {
+            value: value.into(),
+            width,
+            order,
+            pos: 0,
+        }
+    }
+
+    /// Reads the next field, which is `bits` bits wide.
+    ///
+    /// # Panics
+    /// Panics if the fields read so far are wider than the backing integer.
+    pub fn read<T: FromBits>(&mut self, bits: u32) -> Result<T, Error> {
+        assert!(self.pos + bits <= self.width, "bit fields exceed backing");
+
+        let shift = match self.order {
+            | BitOrder::Msb => self.width - self.pos - bits,
+            | BitOrder::Lsb => self.pos,
+        };
+
+        self.pos += bits;
+
+        T::from_bits((self.value >> shift) & mask(bits))
+    }
+}
+
+/// Packs consecutive bit fields into a backing integer that is `width` bits
+/// wide.
+#[derive(Debug, Clone)]
+pub struct BitWriter {
+    value: u64,
+    width: u32,
+    order: BitOrder,
+    pos: u32,
+}
+
+impl BitWriter {
+    /// Creates a writer for an integer that is `width` bits wide.
+    pub fn new(width: u32, order: BitOrder) -> Self {
+        Self {
+            value: 0,
+            width,
+            order,
+            pos: 0,
+        }
+    }
+
+    /// Writes the next field, which is `bits` bits wide.
+    ///
+    /// Fails with [`Error::BitFieldOverflow`] if the value does not fit in
+    /// `bits` bits.
+    ///
+    /// # Panics
+    /// Panics if the fields written so far are wider than the backing integer.
+    pub fn write<T: ToBits + ?Sized>(
+        &mut self,
+        item: &T,
+        bits: u32,
+    ) -> Result<(), Error> {
+        assert!(self.pos + bits <= self.width, "bit fields exceed backing");
+
+        let value = item.to_bits();
+
+        if value & !mask(bits) != 0 {
+            return Err(Error::BitFieldOverflow { bits });
+        }
+
+        let shift = match self.order {
+            | BitOrder::Msb => self.width - self.pos - bits,
+            | BitOrder::Lsb => self.pos,
+        };
+
+        self.value |= value << shift;
+        self.pos += bits;
+
+        Ok(())
+    }
+
+    /// Returns the backing integer holding the written fields.
+    pub fn finish<T: TryFrom<u64>>(self) -> T {
+        T::try_from(self.value)
+            .ok()
+            .expect("bit fields fit in the backing integer")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use super::*;
+    use crate as recode;
+    use crate::{util::EncoderExt, Decoder, Recode};
+
+    #[allow(clippy::enum_variant_names)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Recode)]
+    #[recode(bits = 2)]
+    enum QoS {
+        #[recode(id = 0)]
+        AtMostOnce,
+        #[recode(id = 1)]
+        AtLeastOnce,
+        #[recode(id = 2)]
+        ExactlyOnce,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Recode)]
+    #[recode(bits = 8, error = "crate::Error")]
+    struct FixedHeader {
+        #[recode(bits = 4)]
+        kind: u8,
+        #[recode(bits = 1)]
+        dup: bool,
+        #[recode(bits = 2)]
+        qos: QoS,
+        #[recode(bits = 1)]
+        retain: bool,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Recode)]
+    #[recode(bit_order = "lsb", endian = "little", error = "crate::Error")]
+    struct Register {
+        id: u8,
+        #[recode(bits = 3)]
+        mode: u8,
+        #[recode(bits = 12)]
+        value: u16,
+        #[recode(bits = 1, validate = "unset")]
+        reserved: bool,
+        tail: u8,
+    }
+
+    fn unset<B>(reserved: &bool, _: &B) -> crate::Result<()> {
+        match reserved {
+            | true => Err(crate::Error::NonZeroReserved { offset: 0 }),
+            | false => Ok(()),
+        }
+    }
+
+    #[cfg(feature = "ux")]
+    #[derive(Debug, PartialEq, Eq, Recode)]
+    #[recode(bits = 24, endian = "little", error = "crate::Error")]
+    struct Point {
+        #[recode(bits = 12)]
+        x: u16,
+        #[recode(bits = 12)]
+        y: u16,
+    }
+
+    #[test]
+    fn msb_first_test() {
+        let mut writer = BitWriter::new(8, BitOrder::Msb);
+
+        writer.write(&0b0011u8, 4).unwrap();
+        writer.write(&true, 1).unwrap();
+        writer.write(&0b01u8, 2).unwrap();
+        writer.write(&false, 1).unwrap();
+
+        let value: u8 = writer.finish();
+
+        assert_eq!(value, 0b0011_1010);
+
+        let mut reader = BitReader::new(value, 8, BitOrder::Msb);
+
+        assert_eq!(reader.read::<u8>(4).unwrap(), 0b0011);
+        assert!(reader.read::<bool>(1).unwrap());
+        assert_eq!(reader.read::<u8>(2).unwrap(), 0b01);
+        assert!(!reader.read::<bool>(1).unwrap());
+    }
+
+    #[test]
+    fn lsb_first_test() {
+        let mut writer = BitWriter::new(16, BitOrder::Lsb);
+
+        writer.write(&0x5u16, 4).unwrap();
+        writer.write(&0xABCu16, 12).unwrap();
+
+        let value: u16 = writer.finish();
+
+        assert_eq!(value, 0xABC5);
+
+        let mut reader = BitReader::new(value, 16, BitOrder::Lsb);
+
+        assert_eq!(reader.read::<u16>(4).unwrap(), 0x5);
+        assert_eq!(reader.read::<u16>(12).unwrap(), 0xABC);
+    }
+
+    #[test]
+    fn overflow_test() {
+        let mut writer = BitWriter::new(64, BitOrder::Msb);
+
+        assert!(matches!(
+            writer.write(&0b100u8, 2),
+            Err(Error::BitFieldOverflow { bits: 2 })
+        ));
+        assert!(writer.write(&u64::MAX, 64).is_ok());
+        assert!(matches!(
+            BitReader::new(0b10u8, 8, BitOrder::Lsb).read::<bool>(2),
+            Err(Error::InvalidBool(2))
+        ));
+    }
+
+    #[test]
+    fn derive_test() {
+        let header = FixedHeader {
+            kind: 3,
+            dup: false,
+            qos: QoS::ExactlyOnce,
+            retain: true,
+        };
+        let mut bytes = BytesMut::new();
+
+        header.encode_to(&mut bytes).unwrap();
+
+        assert_eq!(&bytes[..], &[0b0011_0101]);
+        assert_eq!(header.size(), 1);
+        assert_eq!(FixedHeader::decode(&mut bytes).unwrap(), header);
+
+        let mut invalid = BytesMut::from_iter([0b0011_0111]);
+        let err = FixedHeader::decode(&mut invalid).unwrap_err();
+
+        assert!(matches!(err.root(), crate::Error::UnknownTag(3)));
+        assert_eq!(err.context().unwrap().path, ["qos"]);
+        assert!(matches!(
+            FixedHeader { kind: 16, ..header }.encode_to(&mut bytes),
+            Err(crate::Error::BitFieldOverflow { bits: 4 })
+        ));
+    }
+
+    #[test]
+    fn derive_run_test() {
+        let register = Register {
+            id: 0x01,
+            mode: 0b101,
+            value: 0xABC,
+            reserved: false,
+            tail: 0xFF,
+        };
+        let mut bytes = BytesMut::new();
+
+        register.encode_to(&mut bytes).unwrap();
+
+        // 0b0_101010111100_101 (reserved, value, mode), little-endian
+        assert_eq!(&bytes[..], &[0x01, 0xE5, 0x55, 0xFF]);
+        assert_eq!(register.size(), 4);
+        assert_eq!(Register::decode(&mut bytes).unwrap(), register);
+
+        let mut invalid = BytesMut::from_iter([0x01, 0xE5, 0xD5, 0xFF]);
+
+        assert!(matches!(
+            Register::decode(&mut invalid).unwrap_err().root(),
+            crate::Error::NonZeroReserved { offset: 0 }
+        ));
+    }
+
+    #[cfg(feature = "ux")]
+    #[test]
+    fn derive_ux_test() {
+        let point = Point { x: 0xABC, y: 0x123 };
+        let mut bytes = BytesMut::new();
+
+        point.encode_to(&mut bytes).unwrap();
+
+        assert_eq!(&bytes[..], &[0x23, 0xC1, 0xAB]);
+        assert_eq!(point.size(), 3);
+        assert_eq!(Point::decode(&mut bytes).unwrap(), point);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{
+    bytes::{Buf, BufMut},
+    Decoder,
+    Encoder,
+    FixedSize,
+    RawDecoder,
+};
+
+impl<C, T, const N: usize> Decoder<[T; N]> for [C; N]
+where
+    C: Decoder<T>,
+{
+    type Error = C::Error;
+
+    const MAX_SIZE: Option<usize> = crate::util::size::mul_max(C::MAX_SIZE, N);
+    const MIN_SIZE: usize = C::MIN_SIZE * N;
+
+    fn decode<B: Buf>(buf: &mut B) -> Result<[T; N], Self::Error> {
+        let mut items: [Option<T>; N] = std::array::from_fn(|_| None);
+
+        for item in &mut items {
+            *item = Some(C::decode(buf)?);
+        }
+
+        Ok(items.map(|item| item.expect("all items are decoded")))
+    }
+
+    #[inline]
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        N == 0 || C::has_enough_bytes(buf)
+    }
+}
+
+impl<C, T, const N: usize> RawDecoder<[T; N]> for [C; N]
+where
+    C: RawDecoder<T>,
+{
+    type Error = C::Error;
+
+    fn raw_decode<'a>(buf: &'a [u8]) -> Result<([T; N], usize), Self::Error>
+    where
+        [T; N]: 'a,
+    {
+        let mut items: [Option<T>; N] = std::array::from_fn(|_| None);
+        let mut offset = 0;
+
+        for item in &mut items {
+            let (value, rx) = C::raw_decode(&buf[offset..])?;
+
+            *item = Some(value);
+            offset += rx;
+        }
+
+        Ok((
+            items.map(|item| item.expect("all items are decoded")),
+            offset,
+        ))
+    }
+}
+
+impl<C, T, const N: usize> Encoder<[T; N]> for [C; N]
+where
+    C: Encoder<T>,
+{
+    type Error = C::Error;
+
+    const MAX_SIZE: Option<usize> = crate::util::size::mul_max(C::MAX_SIZE, N);
+    const MIN_SIZE: usize = C::MIN_SIZE * N;
+
+    fn encode<B: BufMut>(
+        item: &[T; N],
+        buf: &mut B,
+    ) -> Result<(), Self::Error> {
+        for element in item {
+            C::encode(element, buf)?;
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn size_of(item: &[T; N]) -> usize {
+        item.iter().map(C::size_of).sum()
+    }
+}
+
+impl<C, const N: usize> FixedSize for [C; N]
+where
+    C: FixedSize,
+{
+    const SIZE: usize = C::SIZE * N;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{codec::Le, util::EncoderExt, Decoder, Encoder, RawDecoder};
+
+    #[test]
+    fn byte_array_test() {
+        let magic = *b"\x89PNG";
+        let mut bytes = BytesMut::new();
+
+        assert_eq!(magic.size(), 4);
+
+        magic.encode_to(&mut bytes).unwrap();
+
+        assert_eq!(&bytes[..], b"\x89PNG");
+
+        let (peek, rx): ([u8; 4], usize) =
+            <[u8; 4]>::raw_decode(&bytes).unwrap();
+
+        assert_eq!(rx, 4);
+        assert_eq!(peek, magic);
+        let decoded: [u8; 4] = <[u8; 4]>::decode(&mut bytes).unwrap();
+
+        assert_eq!(decoded, magic);
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn codec_array_test() {
+        type Codec = [Le<u16>; 3];
+
+        let items = [0x0102u16, 0x0304, 0x0506];
+        let mut bytes = BytesMut::new();
+
+        assert_eq!(Codec::size_of(&items), 6);
+
+        Codec::encode(&items, &mut bytes).unwrap();
+
+        assert_eq!(&bytes[..], &[0x02, 0x01, 0x04, 0x03, 0x06, 0x05]);
+        let decoded: [u16; 3] = Codec::decode(&mut bytes.clone()).unwrap();
+
+        assert_eq!(decoded, items);
+        assert!(matches!(
+            <Codec as Decoder<[u16; 3]>>::decode(&mut bytes.split_to(5)),
+            Err(crate::Error::BytesNeeded { .. })
+        ));
+    }
+}
@@ -0,0 +1,350 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+};
+
+use crate::{
+    bytes::{Buf, BufMut},
+    Decoder,
+    Encoder,
+    Error,
+};
+
+/// An encoder/decoder for collections prefixed with their element count.
+///
+/// The count is encoded/decoded with `L`, and each element with `C`. Maps use
+/// a `(K, V)` pair of codecs (e.g. `CountPrefixed<u16, (u8, u32)>`).
+///
+/// Decoding fails with [`Error::CountExceeded`] if the count is larger than
+/// `MAX`. Storage is never pre-allocated based on the decoded count, so a
+/// hostile prefix cannot trigger a huge allocation on its own.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CountPrefixed<L, C, const MAX: usize = { usize::MAX }>(
+    PhantomData<(L, C)>,
+);
+
+/// An encoder/decoder for collections that are not prefixed with their
+/// element count.
+///
+/// Decoding consumes elements with `C` until the buffer is empty, while
+/// encoding writes elements without any prefix. Decoding fails with
+/// [`Error::EmptyElement`] if an element is decoded from zero bytes, as the
+/// buffer would otherwise never be emptied.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Repeated<C>(PhantomData<C>);
+
+impl<L, C, const MAX: usize> CountPrefixed<L, C, MAX> {
+    /// Checks that `count` does not exceed the maximum element count.
+    #[inline]
+    fn check_count(count: usize) -> Result<usize, Error> {
+        if count > MAX {
+            return Err(Error::CountExceeded { count, max: MAX });
+        }
+
+        Ok(count)
+    }
+}
+
+macro_rules! impl_decode_collection {
+    ([$($g:tt)*] $t:ty => $e:ty $(; where $($w:tt)+)?) => {
+        impl<L, C, $($g)*, const MAX: usize> Decoder<$t>
+            for CountPrefixed<L, C, MAX>
+        where
+            L: Decoder<usize>,
+            C: Decoder<$e>,
+            Error: From<<L as Decoder<usize>>::Error>
+                + From<<C as Decoder<$e>>::Error>,
+            $($($w)+)?
+        {
+            type Error = Error;
+
+            fn decode<B: Buf>(buf: &mut B) -> Result<$t, Self::Error> {
+                let count = Self::check_count(L::decode(buf)?)?;
+
+                (0..count)
+                    .map(|_| C::decode(buf).map_err(Into::into))
+                    .collect()
+            }
+        }
+
+        impl<C, $($g)*> Decoder<$t> for Repeated<C>
+        where
+            C: Decoder<$e>,
+            Error: From<<C as Decoder<$e>>::Error>,
+            $($($w)+)?
+        {
+            type Error = Error;
+
+            fn decode<B: Buf>(buf: &mut B) -> Result<$t, Self::Error> {
+                std::iter::from_fn(|| {
+                    let remaining = buf.remaining();
+
+                    (remaining > 0).then(|| {
+                        let element = C::decode(buf)?;
+
+                        if buf.remaining() == remaining {
+                            return Err(Error::EmptyElement);
+                        }
+
+                        Ok(element)
+                    })
+                })
+                .collect()
+            }
+        }
+    };
+}
+
+macro_rules! impl_encode_seq {
+    ($t:ident) => {
+        impl<L, C, T, const MAX: usize> Encoder<$t<T>>
+            for CountPrefixed<L, C, MAX>
+        where
+            L: Encoder<usize>,
+            C: Encoder<T>,
+            Error: From<<L as Encoder<usize>>::Error>
+                + From<<C as Encoder<T>>::Error>,
+        {
+            type Error = Error;
+
+            fn encode<B: BufMut>(
+                item: &$t<T>,
+                buf: &mut B,
+            ) -> Result<(), Self::Error> {
+                L::encode(&Self::check_count(item.len())?, buf)?;
+
+                <Repeated<C> as Encoder<$t<T>>>::encode(item, buf)
+            }
+
+            #[inline]
+            fn size_of(item: &$t<T>) -> usize {
+                L::size_of(&item.len())
+                    + <Repeated<C> as Encoder<$t<T>>>::size_of(item)
+            }
+        }
+
+        impl<C, T> Encoder<$t<T>> for Repeated<C>
+        where
+            C: Encoder<T>,
+            Error: From<<C as Encoder<T>>::Error>,
+        {
+            type Error = Error;
+
+            fn encode<B: BufMut>(
+                item: &$t<T>,
+                buf: &mut B,
+            ) -> Result<(), Self::Error> {
+                for element in item {
+                    C::encode(element, buf)?;
+                }
+
+                Ok(())
+            }
+
+            #[inline]
+            fn size_of(item: &$t<T>) -> usize {
+                item.iter().map(C::size_of).sum()
+            }
+        }
+    };
+}
+
+macro_rules! impl_encode_map {
+    ($t:ident<K, V $(, $s:ident)?>) => {
+        impl<L, CK, CV, K, V, $($s,)? const MAX: usize> Encoder<$t<K, V $(, $s)?>>
+            for CountPrefixed<L, (CK, CV), MAX>
+        where
+            L: Encoder<usize>,
+            CK: Encoder<K>,
+            CV: Encoder<V>,
+            Error: From<<L as Encoder<usize>>::Error>
+                + From<<CK as Encoder<K>>::Error>
+                + From<<CV as Encoder<V>>::Error>,
+        {
+            type Error = Error;
+
+            fn encode<B: BufMut>(
+                item: &$t<K, V $(, $s)?>,
+                buf: &mut B,
+            ) -> Result<(), Self::Error> {
+                L::encode(&Self::check_count(item.len())?, buf)?;
+
+                <Repeated<(CK, CV)> as Encoder<$t<K, V $(, $s)?>>>::encode(
+                    item, buf,
+                )
+            }
+
+            #[inline]
+            fn size_of(item: &$t<K, V $(, $s)?>) -> usize {
+                L::size_of(&item.len())
+                    + <Repeated<(CK, CV)> as Encoder<$t<K, V $(, $s)?>>>::size_of(
+                        item,
+                    )
+            }
+        }
+
+        impl<CK, CV, K, V $(, $s)?> Encoder<$t<K, V $(, $s)?>>
+            for Repeated<(CK, CV)>
+        where
+            CK: Encoder<K>,
+            CV: Encoder<V>,
+            Error: From<<CK as Encoder<K>>::Error>
+                + From<<CV as Encoder<V>>::Error>,
+        {
+            type Error = Error;
+
+            fn encode<B: BufMut>(
+                item: &$t<K, V $(, $s)?>,
+                buf: &mut B,
+            ) -> Result<(), Self::Error> {
+                for (key, value) in item {
+                    CK::encode(key, buf)?;
+                    CV::encode(value, buf)?;
+                }
+
+                Ok(())
+            }
+
+            #[inline]
+            fn size_of(item: &$t<K, V $(, $s)?>) -> usize {
+                item.iter()
+                    .map(|(key, value)| CK::size_of(key) + CV::size_of(value))
+                    .sum()
+            }
+        }
+    };
+}
+
+impl_decode_collection!([T] Vec<T> => T);
+impl_decode_collection!([T] VecDeque<T> => T);
+impl_decode_collection!([K, V] BTreeMap<K, V> => (K, V); where K: Ord);
+impl_decode_collection!(
+    [K, V, S] HashMap<K, V, S> => (K, V);
+    where K: Eq + Hash, S: BuildHasher + Default
+);
+
+impl_encode_seq!(Vec);
+impl_encode_seq!(VecDeque);
+
+impl_encode_map!(BTreeMap<K, V>);
+impl_encode_map!(HashMap<K, V, S>);
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap, VecDeque};
+
+    use bytes::{Bytes, BytesMut};
+    use fake::{Fake, Faker};
+
+    use super::*;
+    use crate::codec::LengthPrefixed;
+
+    #[test]
+    fn vec_test() {
+        let len: usize = (0..1024).fake();
+        let items: Vec<u32> = (0..len).map(|_| Faker.fake()).collect();
+        let mut bytes = BytesMut::new();
+
+        CountPrefixed::<u16, u32>::encode(&items, &mut bytes).unwrap();
+
+        assert_eq!(bytes.len(), 2 + 4 * len);
+        assert_eq!(CountPrefixed::<u16, u32>::size_of(&items), bytes.len());
+        assert_eq!(&bytes[..2], &(len as u16).to_be_bytes());
+
+        let decoded: Vec<u32> =
+            CountPrefixed::<u16, u32>::decode(&mut bytes).unwrap();
+
+        assert_eq!(decoded, items);
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn vec_deque_test() {
+        type Codec = CountPrefixed<u8, LengthPrefixed<u8>>;
+
+        let items: VecDeque<Bytes> =
+            [Bytes::from_static(b"ab"), Bytes::from_static(b"")].into();
+        let mut bytes = BytesMut::new();
+
+        Codec::encode(&items, &mut bytes).unwrap();
+
+        assert_eq!(&bytes[..], &[0x02, 0x02, b'a', b'b', 0x00]);
+        assert_eq!(Codec::size_of(&items), bytes.len());
+
+        let decoded: VecDeque<Bytes> = Codec::decode(&mut bytes).unwrap();
+
+        assert_eq!(decoded, items);
+    }
+
+    #[test]
+    fn map_test() {
+        type Codec = CountPrefixed<u8, (u8, u16)>;
+
+        let btree = BTreeMap::from([(0x01, 0x0203), (0x04, 0x0506)]);
+        let mut bytes = BytesMut::new();
+
+        Codec::encode(&btree, &mut bytes).unwrap();
+
+        assert_eq!(&bytes[..], &[0x02, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06]);
+        assert_eq!(Codec::size_of(&btree), bytes.len());
+
+        let hash: HashMap<u8, u16> = Codec::decode(&mut bytes.clone()).unwrap();
+        let decoded: BTreeMap<u8, u16> = Codec::decode(&mut bytes).unwrap();
+
+        assert_eq!(decoded, btree);
+        assert_eq!(hash, HashMap::from_iter(btree));
+    }
+
+    #[test]
+    fn max_count_test() {
+        type Codec = CountPrefixed<u32, u8, 16>;
+
+        let mut bytes = BytesMut::from_iter([0xFF, 0xFF, 0xFF, 0xFF, 0x00]);
+
+        assert!(matches!(
+            <Codec as Decoder<Vec<u8>>>::decode(&mut bytes),
+            Err(Error::CountExceeded {
+                count: 0xFFFFFFFF,
+                max: 16
+            })
+        ));
+        assert!(matches!(
+            Codec::encode(&vec![0u8; 17], &mut BytesMut::new()),
+            Err(Error::CountExceeded { count: 17, max: 16 })
+        ));
+
+        let mut bytes = BytesMut::from_iter([0x00, 0x00, 0x00, 0x02, 0x00]);
+
+        assert!(matches!(
+            <Codec as Decoder<Vec<u8>>>::decode(&mut bytes),
+            Err(Error::BytesNeeded { .. })
+        ));
+    }
+
+    #[test]
+    fn repeated_test() {
+        let items = vec![0x0102u16, 0x0304, 0x0506];
+        let mut bytes = BytesMut::new();
+
+        Repeated::<u16>::encode(&items, &mut bytes).unwrap();
+
+        assert_eq!(&bytes[..], &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06]);
+        assert_eq!(Repeated::<u16>::size_of(&items), bytes.len());
+
+        let decoded: Vec<u16> = Repeated::<u16>::decode(&mut bytes).unwrap();
+
+        assert_eq!(decoded, items);
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn repeated_empty_element_test() {
+        let mut bytes = BytesMut::from_iter([0x00]);
+
+        assert!(matches!(
+            <Repeated<()> as Decoder<Vec<()>>>::decode(&mut bytes),
+            Err(Error::EmptyElement)
+        ));
+    }
+}
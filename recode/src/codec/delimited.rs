@@ -0,0 +1,439 @@
+use std::{
+    ffi::{CStr, CString},
+    marker::PhantomData,
+};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use crate::{
+    util::{BufExt, Utf8Bytes},
+    Decoder,
+    Encoder,
+    Error,
+};
+
+/// A type that describes the byte sequence ending a [`Delimited`] value.
+pub trait Delimiter {
+    /// The delimiter bytes. This must not be empty.
+    const DELIMITER: &'static [u8];
+}
+
+/// A single-byte [`Delimiter`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SingleByte<const B: u8>;
+
+/// A `\r\n` [`Delimiter`], as used by line-based protocols (e.g. SMTP, HTTP).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Crlf;
+
+impl<const B: u8> Delimiter for SingleByte<B> {
+    const DELIMITER: &'static [u8] = &[B];
+}
+
+impl Delimiter for Crlf {
+    const DELIMITER: &'static [u8] = b"\r\n";
+}
+
+/// An encoder/decoder for buffers that are terminated with a delimiter of
+/// type `D`, instead of being prefixed with their length.
+///
+/// Decoding fails with [`Error::BytesNeeded`] if no delimiter is present in
+/// the buffer yet, or with [`Error::DelimiterNotFound`] if none is found
+/// within the first `MAX` bytes. The delimiter is consumed, but is not part of
+/// the decoded value.
+///
+/// Encoding fails with [`Error::UnexpectedDelimiter`] if the value contains
+/// the delimiter, or with [`Error::LengthExceeded`] if it is longer than `MAX`
+/// bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Delimited<D, const MAX: usize = { usize::MAX }>(PhantomData<D>);
+
+/// A buffer that is terminated with the byte `B`.
+pub type Terminated<const B: u8, const MAX: usize = { usize::MAX }> =
+    Delimited<SingleByte<B>, MAX>;
+
+/// A buffer that is terminated with a `\0` byte, as used for C strings.
+pub type NulTerminated<const MAX: usize = { usize::MAX }> = Terminated<0, MAX>;
+
+impl<D, const MAX: usize> Delimited<D, MAX>
+where
+    D: Delimiter,
+{
+    /// Returns the offset of the first delimiter in `buf`.
+    fn find(buf: &[u8]) -> Option<usize> {
+        match D::DELIMITER {
+            | &[b] => buf.iter().position(|&x| x == b),
+            | delim => buf.windows(delim.len()).position(|w| w == delim),
+        }
+    }
+
+    /// Returns the number of bytes the delimiter is looked for in.
+    fn max_window() -> usize {
+        MAX.saturating_add(D::DELIMITER.len())
+    }
+
+    /// Returns the length of the value at the start of `buf`, excluding its
+    /// delimiter, or `None` if `buf` does not expose enough of its bytes to
+    /// tell (see [`BufExt::peek`]).
+    fn value_len<B: Buf>(buf: &B) -> Option<Result<usize, Error>> {
+        let window = Self::max_window().min(buf.remaining());
+
+        // the scratch buffer is only used if `buf` is not contiguous
+        let mut scratch = Vec::new();
+
+        if buf.chunk().len() < window {
+            scratch.resize(window, 0);
+        }
+
+        // the peeked bytes hold at least `window` bytes
+        let bytes = buf.peek(&mut scratch)?;
+
+        if let Some(len) = Self::find(&bytes[..window]) {
+            return Some(Ok(len));
+        }
+
+        if buf.remaining() >= Self::max_window() {
+            return Some(Err(Error::DelimiterNotFound { max: MAX }));
+        }
+
+        Some(Err(Error::BytesNeeded {
+            needed: 1,
+            full_len: buf.remaining() + 1,
+            available: buf.remaining(),
+        }))
+    }
+
+    /// Splits the value off `buf`, and consumes its delimiter.
+    fn split_value<B: Buf>(buf: &mut B) -> Result<Bytes, Error> {
+        let Some(len) = Self::value_len(buf) else {
+            return Self::scan_value(buf);
+        };
+        let value = buf.copy_to_bytes(len?);
+
+        buf.advance(D::DELIMITER.len());
+
+        Ok(value)
+    }
+
+    /// Reads the value at the start of `buf` byte by byte, and consumes its
+    /// delimiter.
+    ///
+    /// This is used for buffers that do not expose enough of their bytes to
+    /// find the delimiter without consuming them, so unlike
+    /// [`split_value`](Self::split_value), an incomplete value is consumed.
+    fn scan_value<B: Buf>(buf: &mut B) -> Result<Bytes, Error> {
+        let mut value = BytesMut::new();
+
+        while buf.has_remaining() {
+            value.put_u8(buf.get_u8());
+
+            if value.ends_with(D::DELIMITER) {
+                value.truncate(value.len() - D::DELIMITER.len());
+
+                return Ok(value.freeze());
+            }
+
+            if value.len() >= Self::max_window() {
+                return Err(Error::DelimiterNotFound { max: MAX });
+            }
+        }
+
+        Err(Error::BytesNeeded {
+            needed: 1,
+            full_len: value.len() + 1,
+            available: value.len(),
+        })
+    }
+
+    /// Writes `value` followed by the delimiter into `buf`.
+    fn put_value<B: BufMut>(value: &[u8], buf: &mut B) -> Result<(), Error> {
+        if value.len() > MAX {
+            return Err(Error::LengthExceeded {
+                len: value.len(),
+                max: MAX,
+            });
+        }
+
+        if let Some(offset) = Self::find(value) {
+            return Err(Error::UnexpectedDelimiter { offset });
+        }
+
+        buf.put_slice(value);
+        buf.put_slice(D::DELIMITER);
+
+        Ok(())
+    }
+}
+
+impl<D, const MAX: usize> Decoder<BytesMut> for Delimited<D, MAX>
+where
+    D: Delimiter,
+{
+    type Error = Error;
+
+    #[inline]
+    fn decode<B: Buf>(buf: &mut B) -> Result<BytesMut, Self::Error> {
+        Self::split_value(buf).map(BytesMut::from)
+    }
+
+    #[inline]
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        // buffers that do not expose their bytes are left to `decode`
+        !matches!(Self::value_len(buf), Some(Err(Error::BytesNeeded { .. })))
+    }
+}
+
+impl<D, const MAX: usize> Decoder<Bytes> for Delimited<D, MAX>
+where
+    D: Delimiter,
+{
+    type Error = Error;
+
+    #[inline]
+    fn decode<B: Buf>(buf: &mut B) -> Result<Bytes, Self::Error> {
+        Self::split_value(buf)
+    }
+
+    #[inline]
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        <Self as Decoder<BytesMut>>::has_enough_bytes(buf)
+    }
+}
+
+impl<D, const MAX: usize> Decoder<String> for Delimited<D, MAX>
+where
+    D: Delimiter,
+{
+    type Error = Error;
+
+    #[inline]
+    fn decode<B: Buf>(buf: &mut B) -> Result<String, Self::Error> {
+        String::from_utf8(Self::split_value(buf)?.into()).map_err(|e| {
+            Error::InvalidUtf8 {
+                offset: e.utf8_error().valid_up_to(),
+            }
+        })
+    }
+
+    #[inline]
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        <Self as Decoder<BytesMut>>::has_enough_bytes(buf)
+    }
+}
+
+impl<D, const MAX: usize> Decoder<Utf8Bytes> for Delimited<D, MAX>
+where
+    D: Delimiter,
+{
+    type Error = Error;
+
+    #[inline]
+    fn decode<B: Buf>(buf: &mut B) -> Result<Utf8Bytes, Self::Error> {
+        Self::split_value(buf).and_then(Utf8Bytes::try_from)
+    }
+
+    #[inline]
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        <Self as Decoder<BytesMut>>::has_enough_bytes(buf)
+    }
+}
+
+impl<const MAX: usize> Decoder<CString> for NulTerminated<MAX> {
+    type Error = Error;
+
+    #[inline]
+    fn decode<B: Buf>(buf: &mut B) -> Result<CString, Self::Error> {
+        let value = Self::split_value(buf)?;
+
+        // the value is split at the first `\0`, so it cannot contain any
+        Ok(CString::new(Vec::from(value)).expect("no interior nul bytes"))
+    }
+
+    #[inline]
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        <Self as Decoder<BytesMut>>::has_enough_bytes(buf)
+    }
+}
+
+macro_rules! impl_encode {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl<D, const MAX: usize> Encoder<$t> for Delimited<D, MAX>
+            where
+                D: Delimiter,
+            {
+                type Error = Error;
+
+                #[inline]
+                fn encode<B: BufMut>(item: &$t, buf: &mut B) -> Result<(), Self::Error> {
+                    Self::put_value(AsRef::<[u8]>::as_ref(item), buf)
+                }
+
+                #[inline]
+                fn size_of(item: &$t) -> usize {
+                    AsRef::<[u8]>::as_ref(item).len() + D::DELIMITER.len()
+                }
+            }
+        )+
+    };
+}
+
+impl_encode!([u8], str, Vec<u8>, String, Bytes, BytesMut, Utf8Bytes);
+
+impl<const MAX: usize> Encoder<CStr> for NulTerminated<MAX> {
+    type Error = Error;
+
+    #[inline]
+    fn encode<B: BufMut>(item: &CStr, buf: &mut B) -> Result<(), Self::Error> {
+        Self::put_value(item.to_bytes(), buf)
+    }
+
+    #[inline]
+    fn size_of(item: &CStr) -> usize {
+        item.to_bytes_with_nul().len()
+    }
+}
+
+impl<const MAX: usize> Encoder<CString> for NulTerminated<MAX> {
+    type Error = Error;
+
+    #[inline]
+    fn encode<B: BufMut>(
+        item: &CString,
+        buf: &mut B,
+    ) -> Result<(), Self::Error> {
+        <Self as Encoder<CStr>>::encode(item, buf)
+    }
+
+    #[inline]
+    fn size_of(item: &CString) -> usize {
+        <Self as Encoder<CStr>>::size_of(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use bytes::{Bytes, BytesMut};
+
+    use super::*;
+    use crate::util::Segmented;
+
+    #[test]
+    fn nul_terminated_test() {
+        let text = CString::new("ayman").unwrap();
+        let mut bytes = BytesMut::new();
+
+        NulTerminated::<16>::encode(&text, &mut bytes).unwrap();
+        NulTerminated::<16>::encode("abc", &mut bytes).unwrap();
+
+        assert_eq!(NulTerminated::<16>::size_of(&text), 6);
+        assert_eq!(&bytes[..], b"ayman\0abc\0");
+
+        let decoded: CString = NulTerminated::<16>::decode(&mut bytes).unwrap();
+
+        assert_eq!(decoded, text);
+
+        let decoded: String = NulTerminated::<16>::decode(&mut bytes).unwrap();
+
+        assert_eq!(decoded, "abc");
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn crlf_delimited_test() {
+        let mut bytes = BytesMut::from(&b"EHLO example.com\r\nQUIT\r"[..]);
+
+        assert!(<Delimited<Crlf> as Decoder<Bytes>>::has_enough_bytes(
+            &bytes
+        ));
+
+        let line: Bytes = Delimited::<Crlf>::decode(&mut bytes).unwrap();
+
+        assert_eq!(&line[..], b"EHLO example.com");
+        assert_eq!(&bytes[..], b"QUIT\r");
+        assert!(!<Delimited<Crlf> as Decoder<Bytes>>::has_enough_bytes(
+            &bytes
+        ));
+        assert!(matches!(
+            <Delimited<Crlf> as Decoder<Bytes>>::decode(&mut bytes),
+            Err(Error::BytesNeeded {
+                needed: 1,
+                full_len: 6,
+                available: 5
+            })
+        ));
+
+        bytes.extend_from_slice(b"\n");
+
+        let line: String = Delimited::<Crlf>::decode(&mut bytes).unwrap();
+
+        assert_eq!(line, "QUIT");
+        assert!(bytes.is_empty());
+
+        let mut encoded = BytesMut::new();
+
+        Delimited::<Crlf>::encode("QUIT", &mut encoded).unwrap();
+
+        assert_eq!(&encoded[..], b"QUIT\r\n");
+    }
+
+    #[test]
+    fn max_len_test() {
+        let mut bytes = BytesMut::from(&b"abcdef\n"[..]);
+
+        assert!(matches!(
+            <Terminated<b'\n', 4> as Decoder<Bytes>>::decode(&mut bytes),
+            Err(Error::DelimiterNotFound { max: 4 })
+        ));
+
+        let line: Bytes = Terminated::<b'\n', 6>::decode(&mut bytes).unwrap();
+
+        assert_eq!(&line[..], b"abcdef");
+        assert!(matches!(
+            Terminated::<b'\n', 4>::encode("abcdef", &mut BytesMut::new()),
+            Err(Error::LengthExceeded { len: 6, max: 4 })
+        ));
+        assert!(matches!(
+            Terminated::<b'\n'>::encode("ab\ncd", &mut BytesMut::new()),
+            Err(Error::UnexpectedDelimiter { offset: 2 })
+        ));
+    }
+
+    #[test]
+    fn segmented_test() {
+        let mut text = vec![b'a'; 100];
+
+        text.extend_from_slice(b"\r\nb");
+
+        for vectored in [false, true] {
+            let mut buf = Segmented {
+                bytes: &text,
+                vectored,
+            };
+
+            assert!(<Delimited<Crlf> as Decoder<Bytes>>::has_enough_bytes(
+                &buf
+            ));
+
+            let line: Bytes = Delimited::<Crlf>::decode(&mut buf).unwrap();
+
+            assert_eq!(&line[..], &text[..100]);
+            assert!(matches!(
+                <Delimited<Crlf> as Decoder<Bytes>>::decode(&mut buf),
+                Err(Error::BytesNeeded { .. })
+            ));
+
+            let mut buf = Segmented {
+                bytes: &text,
+                vectored,
+            };
+
+            assert!(matches!(
+                <Terminated<b'\n', 64> as Decoder<Bytes>>::decode(&mut buf),
+                Err(Error::DelimiterNotFound { max: 64 })
+            ));
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use std::marker::PhantomData;
+
+/// An encoder/decoder for integers stored in little-endian byte order.
+///
+/// Integers are big-endian by default; this type can be used wherever an
+/// integer codec is expected (e.g. `LengthPrefixed<LittleEndian<u16>>`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LittleEndian<T>(PhantomData<T>);
+
+/// An encoder/decoder for integers stored in big-endian byte order.
+///
+/// This is equivalent to using the integer type itself as the codec, and
+/// exists to make the byte order explicit.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BigEndian<T>(PhantomData<T>);
+
+/// A shorthand for [`LittleEndian`].
+pub type Le<T> = LittleEndian<T>;
+
+/// A shorthand for [`BigEndian`].
+pub type Be<T> = BigEndian<T>;
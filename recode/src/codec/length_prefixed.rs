@@ -3,7 +3,8 @@ use std::marker::PhantomData;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
 use crate::{
-    util::{BufExt, Remaining},
+    util::{BufExt, FixedLength, Remaining, Utf8Bytes},
+    BorrowDecoder,
     Decoder,
     Encoder,
     Error,
@@ -13,31 +14,151 @@ use crate::{
 /// A buffer that is not prefixed with its length.
 pub type Unprefixed = LengthPrefixed<Remaining>;
 
+/// A buffer that is exactly `N` bytes long, and is not prefixed with its
+/// length.
+///
+/// Encoding fails if the buffer is not exactly `N` bytes long; use
+/// [`Padded`](super::Padded) for shorter values.
+pub type Fixed<const N: usize> = LengthPrefixed<FixedLength<N>>;
+
 /// An encoder/decoder for length-prefixed buffers.
 ///
-/// This currently only supports encoding/decoding [`Bytes`](bytes::Bytes).
+/// This supports decoding [`Bytes`], [`BytesMut`], [`String`] and
+/// [`Utf8Bytes`], borrowing `&[u8]` and `&str` from slices (see
+/// [`BorrowDecoder`]), and encoding anything that implements `AsRef<[u8]>`
+/// (including [`str`]). Text is validated to be UTF-8 when decoding.
+///
+/// Decoding [`Bytes`] from [`Bytes`] or [`BytesMut`] buffers does not copy the
+/// value, while decoding [`BytesMut`] copies it unless the buffer is not
+/// shared.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct LengthPrefixed<L>(PhantomData<L>);
 
-impl<L> Decoder<BytesMut> for LengthPrefixed<L>
+impl<L> LengthPrefixed<L>
+where
+    L: Decoder<usize> + RawDecoder<usize>,
+    Error: From<<L as Decoder<usize>>::Error>
+        + From<<L as RawDecoder<usize>>::Error>,
+{
+    /// Splits the value off `buf`, and consumes its length prefix.
+    fn split_value<B: Buf>(buf: &mut B) -> Result<Bytes, Error> {
+        // the prefix is peeked first if possible, so that the buffer is not
+        // advanced if the value is incomplete
+        if let Some(bytes) = buf.peek(&mut [0; 16]) {
+            let (len, rx) = L::raw_decode(bytes)?;
+            let full_len =
+                len.checked_add(rx).ok_or(Error::LengthExceeded {
+                    len,
+                    max: usize::MAX - rx,
+                })?;
+
+            buf.require_n(full_len)?;
+        }
+
+        let remaining = buf.remaining();
+        let len = L::decode(buf)?;
+
+        // otherwise, a value that is incomplete once its prefix is consumed
+        // cannot be decoded again, so this is not reported as `BytesNeeded`
+        if buf.remaining() < len && buf.remaining() < remaining {
+            return Err(Error::LengthExceeded {
+                len,
+                max: buf.remaining(),
+            });
+        }
+
+        buf.require_n(len)?;
+
+        Ok(buf.copy_to_bytes(len))
+    }
+}
+
+impl<L> LengthPrefixed<L>
 where
     L: RawDecoder<usize>,
     Error: From<<L as RawDecoder<usize>>::Error>,
+{
+    /// Returns the value at the start of `buf`, along with the number of
+    /// bytes read (including its length prefix).
+    fn split_slice(buf: &[u8]) -> Result<(&[u8], usize), Error> {
+        let (len, rx) = L::raw_decode(buf)?;
+        let rest = &buf[rx..];
+
+        rest.require_n(len)?;
+
+        Ok((&rest[..len], rx + len))
+    }
+}
+
+impl<L> Decoder<BytesMut> for LengthPrefixed<L>
+where
+    L: Decoder<usize> + RawDecoder<usize>,
+    Error: From<<L as Decoder<usize>>::Error>
+        + From<<L as RawDecoder<usize>>::Error>,
 {
     type Error = Error;
 
+    const MIN_SIZE: usize = <L as Decoder<usize>>::MIN_SIZE;
+
     #[inline]
-    fn decode(buf: &mut BytesMut) -> Result<BytesMut, Self::Error> {
-        let (len, rx) = L::raw_decode(buf.chunk())?;
+    fn decode<B: Buf>(buf: &mut B) -> Result<BytesMut, Self::Error> {
+        Self::split_value(buf).map(BytesMut::from)
+    }
+}
 
-        buf.require_n(len)?;
-        buf.advance(rx);
+impl<L> Decoder<Bytes> for LengthPrefixed<L>
+where
+    L: Decoder<usize> + RawDecoder<usize>,
+    Error: From<<L as Decoder<usize>>::Error>
+        + From<<L as RawDecoder<usize>>::Error>,
+{
+    type Error = Error;
 
-        Ok(buf.split_to(len))
+    const MIN_SIZE: usize = <L as Decoder<usize>>::MIN_SIZE;
+
+    #[inline]
+    fn decode<B: Buf>(buf: &mut B) -> Result<Bytes, Self::Error> {
+        Self::split_value(buf)
     }
 }
 
-impl<L> Decoder<Bytes> for LengthPrefixed<L>
+impl<L> Decoder<String> for LengthPrefixed<L>
+where
+    L: Decoder<usize> + RawDecoder<usize>,
+    Error: From<<L as Decoder<usize>>::Error>
+        + From<<L as RawDecoder<usize>>::Error>,
+{
+    type Error = Error;
+
+    const MIN_SIZE: usize = <L as Decoder<usize>>::MIN_SIZE;
+
+    #[inline]
+    fn decode<B: Buf>(buf: &mut B) -> Result<String, Self::Error> {
+        let bytes = Self::split_value(buf)?;
+
+        String::from_utf8(bytes.into()).map_err(|e| Error::InvalidUtf8 {
+            offset: e.utf8_error().valid_up_to(),
+        })
+    }
+}
+
+impl<L> Decoder<Utf8Bytes> for LengthPrefixed<L>
+where
+    L: Decoder<usize> + RawDecoder<usize>,
+    Error: From<<L as Decoder<usize>>::Error>
+        + From<<L as RawDecoder<usize>>::Error>,
+{
+    type Error = Error;
+
+    const MIN_SIZE: usize = <L as Decoder<usize>>::MIN_SIZE;
+
+    #[inline]
+    fn decode<B: Buf>(buf: &mut B) -> Result<Utf8Bytes, Self::Error> {
+        Self::split_value(buf).and_then(Utf8Bytes::try_from)
+    }
+}
+
+impl<'a, L> BorrowDecoder<'a, &'a [u8]> for LengthPrefixed<L>
 where
     L: RawDecoder<usize>,
     Error: From<<L as RawDecoder<usize>>::Error>,
@@ -45,20 +166,40 @@ where
     type Error = Error;
 
     #[inline]
-    fn decode(buf: &mut BytesMut) -> Result<Bytes, Self::Error> {
-        <Self as Decoder<BytesMut>>::decode(buf).map(BytesMut::freeze)
+    fn borrow_decode(buf: &'a [u8]) -> Result<(&'a [u8], usize), Self::Error> {
+        Self::split_slice(buf)
+    }
+}
+
+impl<'a, L> BorrowDecoder<'a, &'a str> for LengthPrefixed<L>
+where
+    L: RawDecoder<usize>,
+    Error: From<<L as RawDecoder<usize>>::Error>,
+{
+    type Error = Error;
+
+    fn borrow_decode(buf: &'a [u8]) -> Result<(&'a str, usize), Self::Error> {
+        let (value, rx) = Self::split_slice(buf)?;
+        let value =
+            std::str::from_utf8(value).map_err(|e| Error::InvalidUtf8 {
+                offset: e.valid_up_to(),
+            })?;
+
+        Ok((value, rx))
     }
 }
 
 impl<L, T> Encoder<T> for LengthPrefixed<L>
 where
-    T: AsRef<[u8]>,
+    T: AsRef<[u8]> + ?Sized,
     L: Encoder<usize>,
     Error: From<<L as Encoder<usize>>::Error>,
 {
     type Error = Error;
 
-    fn encode(item: &T, buf: &mut BytesMut) -> Result<(), Self::Error> {
+    const MIN_SIZE: usize = <L as Encoder<usize>>::MIN_SIZE;
+
+    fn encode<B: BufMut>(item: &T, buf: &mut B) -> Result<(), Self::Error> {
         let slice = item.as_ref();
 
         L::encode(&slice.len(), buf)?;
@@ -84,8 +225,10 @@ mod tests {
     use crate::codec::ux::*;
     use crate::{
         codec::{length_prefixed::Unprefixed, *},
+        util::{Segmented, Utf8Bytes},
         Decoder,
         Encoder,
+        Error,
     };
 
     #[test]
@@ -108,6 +251,107 @@ mod tests {
         assert_eq!(encoded.as_ref(), buffer.as_ref());
     }
 
+    #[test]
+    fn zero_copy_test() {
+        let src = Bytes::from_static(b"\x03abc\x01d");
+        let mut bytes = src.clone();
+        let mut bytes_mut = BytesMut::from(&src[..]);
+
+        let a: Bytes = LengthPrefixed::<u8>::decode(&mut bytes).unwrap();
+        let b: Bytes = LengthPrefixed::<u8>::decode(&mut bytes_mut).unwrap();
+
+        assert_eq!(a.as_ptr(), src[1..].as_ptr());
+        assert_eq!(b.as_ptr(), bytes_mut.as_ptr().wrapping_sub(3));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn incomplete_test() {
+        let mut chain = bytes::Buf::chain(&b"\x00\x04ab"[..], &b"c"[..]);
+
+        assert!(matches!(
+            <LengthPrefixed<u16> as Decoder<Bytes>>::decode(&mut chain),
+            Err(Error::BytesNeeded {
+                needed: 1,
+                full_len: 6,
+                available: 5
+            })
+        ));
+        assert_eq!(bytes::Buf::remaining(&chain), 5);
+    }
+
+    #[test]
+    fn unpeekable_incomplete_test() {
+        let mut buf = Segmented {
+            bytes: b"\x00\x04abc",
+            vectored: false,
+        };
+
+        assert!(matches!(
+            <LengthPrefixed<u16> as Decoder<Bytes>>::decode(&mut buf),
+            Err(Error::LengthExceeded { len: 4, max: 3 })
+        ));
+
+        let mut buf = Segmented {
+            bytes: b"abc",
+            vectored: false,
+        };
+
+        assert!(matches!(
+            <Fixed<4> as Decoder<Bytes>>::decode(&mut buf),
+            Err(Error::BytesNeeded { needed: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn length_overflow_test() {
+        let mut bytes = BytesMut::from(&[0xFF; 16][..]);
+
+        assert!(matches!(
+            <LengthPrefixed<u64> as Decoder<Bytes>>::decode(&mut bytes),
+            Err(Error::LengthExceeded { .. })
+        ));
+
+        bytes[9] = 0x01;
+
+        assert!(matches!(
+            <LengthPrefixed<Uleb128> as Decoder<Bytes>>::decode(&mut bytes),
+            Err(Error::LengthExceeded { .. })
+        ));
+        assert_eq!(bytes.len(), 16);
+    }
+
+    #[test]
+    fn fixed_test() {
+        let mut bytes = BytesMut::from(&b"abcdef"[..]);
+
+        let decoded: Bytes = Fixed::<4>::decode(&mut bytes).unwrap();
+
+        assert_eq!(&decoded[..], b"abcd");
+        assert!(matches!(
+            <Fixed<4> as Decoder<Bytes>>::decode(&mut bytes),
+            Err(Error::BytesNeeded { needed: 2, .. })
+        ));
+
+        let mut encoded = BytesMut::new();
+
+        Fixed::<4>::encode(&decoded, &mut encoded).unwrap();
+
+        assert_eq!(Fixed::<4>::size_of(&decoded), 4);
+        assert_eq!(&encoded[..], b"abcd");
+        assert!(matches!(
+            Fixed::<4>::encode("abcde", &mut encoded),
+            Err(Error::LengthExceeded { len: 5, max: 4 })
+        ));
+        assert!(matches!(
+            Fixed::<4>::encode("abc", &mut encoded),
+            Err(Error::InvalidLength {
+                len: 3,
+                expected: 4
+            })
+        ));
+    }
+
     #[test]
     fn whole_prefix_test() {
         let full_len: usize = (128..=10240).fake();
@@ -138,6 +382,46 @@ mod tests {
         assert_eq!(decoded.as_ref(), buffer.as_ref());
     }
 
+    #[test]
+    fn string_test() {
+        let text = "القاضي";
+        let mut bytes = BytesMut::new();
+
+        LengthPrefixed::<u16>::encode(text, &mut bytes).unwrap();
+        LengthPrefixed::<Uleb128>::encode(&text.to_owned(), &mut bytes)
+            .unwrap();
+
+        assert_eq!(LengthPrefixed::<u16>::size_of(text), 2 + 12);
+        assert_eq!(bytes.len(), (2 + 12) + (1 + 12));
+
+        let decoded: String =
+            LengthPrefixed::<u16>::decode(&mut bytes).unwrap();
+
+        assert_eq!(decoded, text);
+
+        let ptr = bytes[1..].as_ptr();
+        let decoded: Utf8Bytes =
+            LengthPrefixed::<Uleb128>::decode(&mut bytes).unwrap();
+
+        assert_eq!(&*decoded, text);
+        assert_eq!(decoded.as_ptr(), ptr);
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn invalid_utf8_test() {
+        let mut bytes = BytesMut::from_iter([0x04, b'a', b'b', 0xC3, 0x28]);
+
+        assert!(matches!(
+            <LengthPrefixed<u8> as Decoder<String>>::decode(&mut bytes.clone()),
+            Err(Error::InvalidUtf8 { offset: 2 })
+        ));
+        assert!(matches!(
+            <LengthPrefixed<u8> as Decoder<Utf8Bytes>>::decode(&mut bytes),
+            Err(Error::InvalidUtf8 { offset: 2 })
+        ));
+    }
+
     macro_rules! test_ux_len {
         ($t:ty; size: $s:literal; rep: $r:ty ) => {
             paste::paste! {
@@ -1,13 +1,46 @@
+mod array;
+mod collection;
+mod delimited;
+mod endian;
 mod length_prefixed;
+mod nested;
 mod number;
+mod optional;
+mod padded;
+mod primitive;
+mod tuple;
+mod varint;
 
 #[cfg(feature = "ux")]
 mod ux;
 
 #[doc(inline)]
-pub use length_prefixed::{LengthPrefixed, Unprefixed};
+pub use collection::{CountPrefixed, Repeated};
+#[doc(inline)]
+pub use delimited::{
+    Crlf,
+    Delimited,
+    Delimiter,
+    NulTerminated,
+    SingleByte,
+    Terminated,
+};
+#[doc(inline)]
+pub use endian::{Be, BigEndian, Le, LittleEndian};
+#[doc(inline)]
+pub use length_prefixed::{Fixed, LengthPrefixed, Unprefixed};
+#[doc(inline)]
+pub use nested::Nested;
 #[doc(inline)]
 pub use number::*;
+#[doc(inline)]
+pub use optional::{FlagPrefixed, IfRemaining, Sentinel};
+#[doc(inline)]
+pub use padded::Padded;
+#[doc(inline)]
+pub use primitive::{LenientBool, Utf8Char};
+#[doc(inline)]
+pub use varint::{MqttVarInt, QuicVarInt, Sleb128, Uleb128, ZigZag};
 
 #[cfg(feature = "ux")]
 pub use self::ux::*;
@@ -0,0 +1,194 @@
+use std::marker::PhantomData;
+
+use super::LengthPrefixed;
+use crate::{
+    bytes::{Buf, BufMut, Bytes},
+    util::BufExt,
+    Decoder,
+    Encoder,
+    Error,
+    RawDecoder,
+};
+
+/// An encoder/decoder for values that are prefixed with their length in
+/// bytes.
+///
+/// Decoding reads the length with `L`, splits off exactly that many bytes and
+/// decodes the value from them with `C`. Decoding fails with
+/// [`Error::TrailingBytes`] if `C` does not consume the whole frame, and with
+/// [`Error::TruncatedFrame`] if `C` needs more bytes than the frame holds.
+///
+/// The length written on encoding is computed with `C::size_of`, so this can
+/// be used for a single nested value (e.g. `Nested<u16, Header>`) as well as
+/// for a list of values (e.g. `Nested<u32, Repeated<Record>>`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Nested<L, C>(PhantomData<(L, C)>);
+
+impl<L, C, T> Decoder<T> for Nested<L, C>
+where
+    L: Decoder<usize> + RawDecoder<usize>,
+    C: Decoder<T>,
+    Error: From<<L as Decoder<usize>>::Error>
+        + From<<L as RawDecoder<usize>>::Error>
+        + From<<C as Decoder<T>>::Error>,
+{
+    type Error = Error;
+
+    const MIN_SIZE: usize = <L as Decoder<usize>>::MIN_SIZE;
+
+    fn decode<B: Buf>(buf: &mut B) -> Result<T, Self::Error> {
+        let mut frame: Bytes = LengthPrefixed::<L>::decode(buf)?;
+        let len = frame.len();
+        let item = C::decode(&mut frame).map_err(|e| {
+            let mut e = Error::from(e);
+
+            e.truncate_frame(len);
+            e
+        })?;
+
+        if frame.has_remaining() {
+            return Err(Error::TrailingBytes(frame.remaining()));
+        }
+
+        Ok(item)
+    }
+
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        // buffers that do not expose their bytes are left to `decode`
+        buf.peek(&mut [0; 16]).is_none_or(|bytes| {
+            L::raw_decode(bytes)
+                .is_ok_and(|(len, rx)| buf.remaining() - rx >= len)
+        })
+    }
+}
+
+impl<L, C, T> Encoder<T> for Nested<L, C>
+where
+    L: Encoder<usize>,
+    C: Encoder<T>,
+    Error: From<<L as Encoder<usize>>::Error> + From<<C as Encoder<T>>::Error>,
+{
+    type Error = Error;
+
+    const MIN_SIZE: usize = <L as Encoder<usize>>::MIN_SIZE;
+
+    fn encode<B: BufMut>(item: &T, buf: &mut B) -> Result<(), Self::Error> {
+        L::encode(&C::size_of(item), buf)?;
+        C::encode(item, buf)?;
+
+        Ok(())
+    }
+
+    #[inline]
+    fn size_of(item: &T) -> usize {
+        let len = C::size_of(item);
+
+        L::size_of(&len) + len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+
+    use crate as recode;
+    use crate::{
+        codec::{LengthPrefixed, Nested, Repeated},
+        Decoder,
+        Encoder,
+        Error,
+        Recode,
+    };
+
+    #[derive(Debug, PartialEq, Eq, Recode)]
+    #[recode(error = "crate::Error")]
+    struct Record {
+        id: u8,
+        #[recode(with = "LengthPrefixed::<u8>")]
+        value: Bytes,
+    }
+
+    #[test]
+    fn nested_struct_test() {
+        let record = Record {
+            id: 0x01,
+            value: Bytes::from_static(b"abc"),
+        };
+        let mut bytes = BytesMut::new();
+
+        Nested::<u16, Record>::encode(&record, &mut bytes).unwrap();
+
+        assert_eq!(&bytes[..], &[0x00, 0x05, 0x01, 0x03, b'a', b'b', b'c']);
+        assert_eq!(Nested::<u16, Record>::size_of(&record), bytes.len());
+        assert!(Nested::<u16, Record>::has_enough_bytes(&bytes));
+        assert!(!Nested::<u16, Record>::has_enough_bytes(&&bytes[..6]));
+
+        let decoded = Nested::<u16, Record>::decode(&mut bytes).unwrap();
+
+        assert_eq!(decoded, record);
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn length_overflow_test() {
+        let mut bytes = BytesMut::from(&[0xFF; 16][..]);
+
+        assert!(matches!(
+            Nested::<u64, Record>::decode(&mut bytes),
+            Err(Error::LengthExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn nested_list_test() {
+        type Codec = Nested<u32, Repeated<Record>>;
+
+        let records = vec![
+            Record {
+                id: 0x01,
+                value: Bytes::from_static(b"a"),
+            },
+            Record {
+                id: 0x02,
+                value: Bytes::new(),
+            },
+        ];
+        let mut bytes = BytesMut::new();
+
+        Codec::encode(&records, &mut bytes).unwrap();
+        bytes.extend_from_slice(b"rest");
+
+        assert_eq!(
+            &bytes[..],
+            &[
+                0, 0, 0, 5, 0x01, 0x01, b'a', 0x02, 0x00, b'r', b'e', b's',
+                b't'
+            ]
+        );
+
+        let decoded: Vec<Record> = Codec::decode(&mut bytes).unwrap();
+
+        assert_eq!(decoded, records);
+        assert_eq!(&bytes[..], b"rest");
+    }
+
+    #[test]
+    fn nested_invalid_test() {
+        let mut trailing = BytesMut::from_iter([0x03, 0x01, 0x00, 0xFF]);
+        let mut missing = BytesMut::from_iter([0x05, 0x01, 0x00]);
+        let mut short = BytesMut::from_iter([0x02, 0x01, 0x02, b'a']);
+
+        assert!(matches!(
+            Nested::<u8, Record>::decode(&mut trailing),
+            Err(Error::TrailingBytes(1))
+        ));
+        assert!(matches!(
+            Nested::<u8, Record>::decode(&mut missing),
+            Err(Error::BytesNeeded { needed: 3, .. })
+        ));
+        assert!(matches!(
+            Nested::<u8, Record>::decode(&mut short).unwrap_err().root(),
+            Error::TruncatedFrame { len: 2 }
+        ));
+    }
+}
@@ -1,8 +1,10 @@
+use super::{BigEndian, LittleEndian};
 use crate::{
-    bytes::{Buf, BufMut, BytesMut},
-    util::EncoderExt,
+    bytes::{Buf, BufMut},
+    util::BufExt,
     Decoder,
     Encoder,
+    FixedSize,
     RawDecoder,
 };
 
@@ -31,120 +33,146 @@ impl std::fmt::Display for TryFromIntError {
 }
 
 impl std::error::Error for TryFromIntError {}
-macro_rules! impl_int {
-    ($t:ty) => {
-        paste::paste! {
-            impl Decoder for $t {
-                type Error = crate::Error;
 
-                #[inline]
-                fn decode(buf: &mut BytesMut) -> Result<Self, Self::Error> {
-                    let (num, off) = Self::raw_decode(buf)?;
-                    buf.advance(off);
+macro_rules! impl_num {
+    ($t:ty; codec: $c:ty; from: $from:ident; to: $to:ident) => {
+        impl Decoder<$t> for $c {
+            type Error = crate::Error;
 
-                    Ok(num)
-                }
+            const MIN_SIZE: usize = std::mem::size_of::<$t>();
+            const MAX_SIZE: Option<usize> = Some(std::mem::size_of::<$t>());
 
-                #[inline]
-                fn has_enough_bytes(buf: &BytesMut) -> bool {
-                    buf.remaining() >= std::mem::size_of::<$t>()
-                }
+            #[inline]
+            fn decode<B: Buf>(buf: &mut B) -> Result<$t, Self::Error> {
+                buf.decode_fixed::<Self, $t>(std::mem::size_of::<$t>())
             }
 
-            impl RawDecoder for $t {
-                type Error = crate::Error;
-
-                fn raw_decode<'a>(
-                    buf: &'a [u8]
-                ) -> Result<($t, usize), Self::Error>
-                where
-                    $t: 'a
-                {
-                    const FULL_LEN: usize = std::mem::size_of::<$t>();
-
-                    if buf.len() < FULL_LEN {
-                        return Err(crate::Error::BytesNeeded {
-                            needed: FULL_LEN - buf.remaining(),
-                            full_len: FULL_LEN,
-                            available: buf.remaining(),
-                        });
-                    }
-
-                    let arr: [u8; FULL_LEN] = buf[..FULL_LEN]
-                        .try_into()
-                        .unwrap();
+            #[inline]
+            fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+                buf.remaining() >= std::mem::size_of::<$t>()
+            }
+        }
 
-                    Ok((<$t>::from_be_bytes(arr), FULL_LEN))
+        impl RawDecoder<$t> for $c {
+            type Error = crate::Error;
+
+            fn raw_decode<'a>(
+                buf: &'a [u8]
+            ) -> Result<($t, usize), Self::Error>
+            where
+                $t: 'a
+            {
+                const FULL_LEN: usize = std::mem::size_of::<$t>();
+
+                if buf.len() < FULL_LEN {
+                    return Err(crate::Error::BytesNeeded {
+                        needed: FULL_LEN - buf.remaining(),
+                        full_len: FULL_LEN,
+                        available: buf.remaining(),
+                    });
                 }
+
+                let arr: [u8; FULL_LEN] = buf[..FULL_LEN]
+                    .try_into()
+                    .unwrap();
+
+                Ok((<$t>::$from(arr), FULL_LEN))
             }
+        }
 
-            impl Encoder for $t {
-                type Error = std::convert::Infallible;
+        impl Encoder<$t> for $c {
+            type Error = std::convert::Infallible;
 
-                #[inline]
-                fn encode(item: &$t, buf: &mut BytesMut) -> Result<(), Self::Error> {
-                    buf.[<put_ $t>](*item);
+            const MIN_SIZE: usize = std::mem::size_of::<$t>();
+            const MAX_SIZE: Option<usize> = Some(std::mem::size_of::<$t>());
 
-                    Ok(())
-                }
+            #[inline]
+            fn encode<B: BufMut>(item: &$t, buf: &mut B) -> Result<(), Self::Error> {
+                buf.put_slice(&item.$to());
 
-                #[inline]
-                fn size_of(_: &$t) -> usize {
-                    std::mem::size_of::<$t>()
-                }
+                Ok(())
             }
 
-            impl Decoder<usize> for $t {
-                type Error = crate::Error;
+            #[inline]
+            fn size_of(_: &$t) -> usize {
+                std::mem::size_of::<$t>()
+            }
+        }
 
-                #[inline]
-                fn decode(buf: &mut BytesMut) -> Result<usize, Self::Error> {
-                    usize::try_from(<Self as Decoder>::decode(buf)?)
-                        .map_err(TryFromIntError::from)
-                        .map_err(Into::into)
-                }
+        impl FixedSize for $c {
+            const SIZE: usize = std::mem::size_of::<$t>();
+        }
+    };
+    ($t:ty) => {
+        impl_num!($t; codec: $t; from: from_be_bytes; to: to_be_bytes);
+        impl_num!($t; codec: BigEndian<$t>; from: from_be_bytes; to: to_be_bytes);
+        impl_num!($t; codec: LittleEndian<$t>; from: from_le_bytes; to: to_le_bytes);
+    };
+}
 
-                #[inline]
-                fn has_enough_bytes(buf: &BytesMut) -> bool {
-                    <Self as Decoder>::has_enough_bytes(buf)
-                }
+macro_rules! impl_int {
+    ($t:ty; codec: $c:ty) => {
+        impl Decoder<usize> for $c {
+            type Error = crate::Error;
+
+            const MIN_SIZE: usize = <Self as Decoder<$t>>::MIN_SIZE;
+            const MAX_SIZE: Option<usize> = <Self as Decoder<$t>>::MAX_SIZE;
+
+            #[inline]
+            fn decode<B: Buf>(buf: &mut B) -> Result<usize, Self::Error> {
+                usize::try_from(<Self as Decoder<$t>>::decode(buf)?)
+                    .map_err(TryFromIntError::from)
+                    .map_err(Into::into)
             }
 
-            impl RawDecoder<usize> for $t {
-                type Error = crate::Error;
+            #[inline]
+            fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+                <Self as Decoder<$t>>::has_enough_bytes(buf)
+            }
+        }
 
-                #[inline]
-                fn raw_decode<'a>(
-                    buf: &'a [u8]
-                ) -> Result<(usize, usize), Self::Error>
-                where
-                    $t: 'a
-                {
-                    let (value, rx) = <Self as RawDecoder>::raw_decode(buf)?;
-                    let value = value.try_into().map_err(TryFromIntError::from)?;
+        impl RawDecoder<usize> for $c {
+            type Error = crate::Error;
 
-                    Ok((value, rx))
-                }
+            #[inline]
+            fn raw_decode<'a>(
+                buf: &'a [u8]
+            ) -> Result<(usize, usize), Self::Error>
+            where
+                $t: 'a
+            {
+                let (value, rx) = <Self as RawDecoder<$t>>::raw_decode(buf)?;
+                let value = value.try_into().map_err(TryFromIntError::from)?;
+
+                Ok((value, rx))
             }
+        }
 
-            impl Encoder<usize> for $t {
-                type Error = crate::Error;
+        impl Encoder<usize> for $c {
+            type Error = crate::Error;
 
-                #[inline]
-                fn encode(item: &usize, buf: &mut BytesMut) -> Result<(), Self::Error> {
-                    Self::try_from(*item)
-                        .map_err(TryFromIntError::from)?
-                        .encode_to(buf)
-                        .map_err(Into::into)
-                }
+            const MIN_SIZE: usize = <Self as Encoder<$t>>::MIN_SIZE;
+            const MAX_SIZE: Option<usize> = <Self as Encoder<$t>>::MAX_SIZE;
 
-                #[inline]
-                fn size_of(_: &usize) -> usize {
-                    std::mem::size_of::<$t>()
-                }
+            #[inline]
+            fn encode<B: BufMut>(item: &usize, buf: &mut B) -> Result<(), Self::Error> {
+                let value = <$t>::try_from(*item).map_err(TryFromIntError::from)?;
+
+                <Self as Encoder<$t>>::encode(&value, buf).map_err(Into::into)
+            }
+
+            #[inline]
+            fn size_of(_: &usize) -> usize {
+                std::mem::size_of::<$t>()
             }
         }
     };
+    ($t:ty) => {
+        impl_num!($t);
+        impl_int!($t; codec: $t);
+        impl_int!($t; codec: BigEndian<$t>);
+        impl_int!($t; codec: LittleEndian<$t>);
+    };
 }
 
 impl_int!(i8);
@@ -162,12 +190,26 @@ impl_int!(u64);
 impl_int!(i128);
 impl_int!(u128);
 
+impl_num!(f32);
+impl_num!(f64);
+
+#[cfg(feature = "half")]
+impl_num!(half::f16);
+#[cfg(feature = "half")]
+impl_num!(half::bf16);
+
 #[cfg(test)]
 mod tests {
     use bytes::BytesMut;
     use fake::Fake;
 
-    use crate::util::EncoderExt;
+    use crate::{
+        codec::{Le, LengthPrefixed},
+        util::EncoderExt,
+        Decoder,
+        Encoder,
+        RawDecoder,
+    };
 
     macro_rules! test_int {
         ($t:ty) => {
@@ -196,6 +238,31 @@ mod tests {
                     assert_eq!(LEN, bytes.len());
                     assert_eq!(&value.to_be_bytes()[..], &bytes[..]);
                 }
+
+                #[test]
+                fn [<test_le_ $t>]() {
+                    const LEN: usize = std::mem::size_of::<$t>();
+
+                    let value: $t = (0..<$t>::MAX).fake();
+                    let mut bytes = BytesMut::new();
+
+                    assert_eq!(LEN, Le::<$t>::size_of(&value));
+
+                    Le::<$t>::encode(&value, &mut bytes).unwrap();
+
+                    assert_eq!(&value.to_le_bytes()[..], &bytes[..]);
+
+                    let (peek, rx): ($t, usize) =
+                        Le::<$t>::raw_decode(&bytes).unwrap();
+
+                    assert_eq!(rx, LEN);
+                    assert_eq!(peek, value);
+
+                    let decoded: $t = Le::<$t>::decode(&mut bytes).unwrap();
+
+                    assert_eq!(decoded, value);
+                    assert!(bytes.is_empty());
+                }
             }
         };
     }
@@ -214,4 +281,77 @@ mod tests {
 
     test_int!(i128);
     test_int!(u128);
+
+    #[test]
+    fn le_length_prefix_test() {
+        let mut bytes = BytesMut::new();
+
+        LengthPrefixed::<Le<u16>>::encode(&b"abc", &mut bytes).unwrap();
+
+        assert_eq!(&bytes[..], &[0x03, 0x00, b'a', b'b', b'c']);
+
+        let decoded: BytesMut =
+            LengthPrefixed::<Le<u16>>::decode(&mut bytes).unwrap();
+
+        assert_eq!(&decoded[..], b"abc");
+    }
+
+    macro_rules! test_float {
+        ($name:ident; $t:ty; $($value:expr),+ $(,)?) => {
+            #[test]
+            fn $name() {
+                const LEN: usize = std::mem::size_of::<$t>();
+
+                $(
+                    let value: $t = $value;
+                    let mut bytes = BytesMut::new();
+
+                    assert_eq!(LEN, value.size());
+                    assert_eq!(LEN, Le::<$t>::size_of(&value));
+
+                    value.encode_to(&mut bytes).unwrap();
+                    Le::<$t>::encode(&value, &mut bytes).unwrap();
+
+                    assert_eq!(&value.to_be_bytes()[..], &bytes[..LEN]);
+                    assert_eq!(&value.to_le_bytes()[..], &bytes[LEN..]);
+
+                    let (peek, rx): ($t, usize) =
+                        <$t>::raw_decode(&bytes).unwrap();
+
+                    assert_eq!(rx, LEN);
+                    assert_eq!(peek.to_bits(), value.to_bits());
+
+                    let be: $t = <$t>::decode(&mut bytes).unwrap();
+                    let le: $t = Le::<$t>::decode(&mut bytes).unwrap();
+
+                    assert_eq!(be.to_bits(), value.to_bits());
+                    assert_eq!(le.to_bits(), value.to_bits());
+                    assert!(bytes.is_empty());
+                )+
+            }
+        };
+    }
+
+    test_float!(test_f32; f32; 0.0, -1.5, f32::MIN_POSITIVE, f32::INFINITY, f32::NAN);
+    test_float!(test_f64; f64; 0.0, -1.5, f64::MIN_POSITIVE, f64::INFINITY, f64::NAN);
+
+    #[cfg(feature = "half")]
+    test_float!(
+        test_f16;
+        half::f16;
+        half::f16::ZERO,
+        half::f16::from_f32(-1.5),
+        half::f16::INFINITY,
+        half::f16::NAN,
+    );
+
+    #[cfg(feature = "half")]
+    test_float!(
+        test_bf16;
+        half::bf16;
+        half::bf16::ZERO,
+        half::bf16::from_f32(-1.5),
+        half::bf16::INFINITY,
+        half::bf16::NAN,
+    );
 }
@@ -0,0 +1,305 @@
+use std::marker::PhantomData;
+
+use crate::{
+    bytes::{Buf, BufMut},
+    codec::number::TryFromIntError,
+    Decoder,
+    Encoder,
+    Error,
+};
+
+/// An encoder/decoder for [`Option`] values that are prefixed with a presence
+/// flag of type `F`.
+///
+/// A flag of `0` means `None`, and a flag of `1` means the value follows,
+/// encoded with `C`. Any other flag fails decoding with
+/// [`Error::InvalidFlag`]. The flag can also be a `bool` (or
+/// [`LenientBool`](super::LenientBool)) byte.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlagPrefixed<F, C>(PhantomData<(F, C)>);
+
+/// An encoder/decoder for [`Option`] values where `None` is represented by the
+/// reserved value `V` (e.g. `Sentinel<u16, 0xFFFF>`).
+///
+/// Encoding `Some` of the reserved value fails with
+/// [`Error::ReservedValue`], as it would decode as `None`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sentinel<C, const V: i128>(PhantomData<C>);
+
+/// An encoder/decoder for [`Option`] values that are only present if there are
+/// bytes remaining in the buffer, as used for trailing optional fields.
+///
+/// `None` is encoded as nothing, so this should only be used for the last
+/// value of a buffer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IfRemaining<C>(PhantomData<C>);
+
+impl<F, C, T> Decoder<Option<T>> for FlagPrefixed<F, C>
+where
+    F: Decoder<usize>,
+    C: Decoder<T>,
+    Error: From<<F as Decoder<usize>>::Error>,
+    Error: From<<C as Decoder<T>>::Error>,
+{
+    type Error = Error;
+
+    fn decode<B: Buf>(buf: &mut B) -> Result<Option<T>, Self::Error> {
+        match F::decode(buf)? {
+            | 0 => Ok(None),
+            | 1 => Ok(Some(C::decode(buf)?)),
+            | flag => Err(Error::InvalidFlag(flag)),
+        }
+    }
+
+    #[inline]
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        F::has_enough_bytes(buf)
+    }
+}
+
+impl<F, C, T> Encoder<Option<T>> for FlagPrefixed<F, C>
+where
+    F: Encoder<usize>,
+    C: Encoder<T>,
+    Error: From<<F as Encoder<usize>>::Error>,
+    Error: From<<C as Encoder<T>>::Error>,
+{
+    type Error = Error;
+
+    fn encode<B: BufMut>(
+        item: &Option<T>,
+        buf: &mut B,
+    ) -> Result<(), Self::Error> {
+        F::encode(&usize::from(item.is_some()), buf)?;
+
+        if let Some(value) = item {
+            C::encode(value, buf)?;
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn size_of(item: &Option<T>) -> usize {
+        F::size_of(&usize::from(item.is_some()))
+            + item.as_ref().map_or(0, C::size_of)
+    }
+}
+
+impl<C, const V: i128> Sentinel<C, V> {
+    /// Returns the reserved value, as a `T`.
+    fn sentinel<T>() -> Result<T, Error>
+    where
+        T: TryFrom<i128>,
+    {
+        T::try_from(V).map_err(|_| TryFromIntError(()).into())
+    }
+}
+
+impl<C, T, const V: i128> Decoder<Option<T>> for Sentinel<C, V>
+where
+    C: Decoder<T>,
+    T: TryFrom<i128> + PartialEq,
+    Error: From<<C as Decoder<T>>::Error>,
+{
+    type Error = Error;
+
+    fn decode<B: Buf>(buf: &mut B) -> Result<Option<T>, Self::Error> {
+        let value = C::decode(buf)?;
+
+        if value == Self::sentinel()? {
+            return Ok(None);
+        }
+
+        Ok(Some(value))
+    }
+
+    #[inline]
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        C::has_enough_bytes(buf)
+    }
+}
+
+impl<C, T, const V: i128> Encoder<Option<T>> for Sentinel<C, V>
+where
+    C: Encoder<T>,
+    T: TryFrom<i128> + PartialEq,
+    Error: From<<C as Encoder<T>>::Error>,
+{
+    type Error = Error;
+
+    fn encode<B: BufMut>(
+        item: &Option<T>,
+        buf: &mut B,
+    ) -> Result<(), Self::Error> {
+        let sentinel = Self::sentinel()?;
+
+        match item {
+            | Some(value) if *value == sentinel => Err(Error::ReservedValue),
+            | Some(value) => C::encode(value, buf).map_err(Into::into),
+            | None => C::encode(&sentinel, buf).map_err(Into::into),
+        }
+    }
+
+    #[inline]
+    fn size_of(item: &Option<T>) -> usize {
+        match item {
+            | Some(value) => C::size_of(value),
+            | None => Self::sentinel().map_or(0, |v| C::size_of(&v)),
+        }
+    }
+}
+
+impl<C, T> Decoder<Option<T>> for IfRemaining<C>
+where
+    C: Decoder<T>,
+{
+    type Error = C::Error;
+
+    #[inline]
+    fn decode<B: Buf>(buf: &mut B) -> Result<Option<T>, Self::Error> {
+        if !buf.has_remaining() {
+            return Ok(None);
+        }
+
+        C::decode(buf).map(Some)
+    }
+
+    #[inline]
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        !buf.has_remaining() || C::has_enough_bytes(buf)
+    }
+}
+
+impl<C, T> Encoder<Option<T>> for IfRemaining<C>
+where
+    C: Encoder<T>,
+{
+    type Error = C::Error;
+
+    #[inline]
+    fn encode<B: BufMut>(
+        item: &Option<T>,
+        buf: &mut B,
+    ) -> Result<(), Self::Error> {
+        match item {
+            | Some(value) => C::encode(value, buf),
+            | None => Ok(()),
+        }
+    }
+
+    #[inline]
+    fn size_of(item: &Option<T>) -> usize {
+        item.as_ref().map_or(0, C::size_of)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+
+    use crate as recode;
+    use crate::{
+        codec::{
+            FlagPrefixed,
+            IfRemaining,
+            LengthPrefixed,
+            LenientBool,
+            Sentinel,
+        },
+        util::EncoderExt,
+        Decoder,
+        Encoder,
+        Error,
+        Recode,
+    };
+
+    #[derive(Debug, PartialEq, Eq, Recode)]
+    #[recode(error = "crate::Error")]
+    struct Entry {
+        #[recode(with = "FlagPrefixed::<u8, LengthPrefixed<u8>>")]
+        name: Option<Bytes>,
+        #[recode(with = "Sentinel::<u16, 0xFFFF>")]
+        parent: Option<u16>,
+        #[recode(with = "IfRemaining::<u32>")]
+        checksum: Option<u32>,
+    }
+
+    #[test]
+    fn optional_struct_test() {
+        let entry = Entry {
+            name: Some(Bytes::from_static(b"ab")),
+            parent: None,
+            checksum: Some(0x01020304),
+        };
+        let mut bytes = BytesMut::new();
+
+        entry.encode_to(&mut bytes).unwrap();
+
+        assert_eq!(
+            &bytes[..],
+            &[0x01, 0x02, b'a', b'b', 0xFF, 0xFF, 0x01, 0x02, 0x03, 0x04]
+        );
+        assert_eq!(Entry::size_of(&entry), bytes.len());
+        assert_eq!(Entry::decode(&mut bytes).unwrap(), entry);
+
+        let entry = Entry {
+            name: None,
+            parent: Some(0x0102),
+            checksum: None,
+        };
+
+        entry.encode_to(&mut bytes).unwrap();
+
+        assert_eq!(&bytes[..], &[0x00, 0x01, 0x02]);
+        assert_eq!(Entry::size_of(&entry), bytes.len());
+        assert_eq!(Entry::decode(&mut bytes).unwrap(), entry);
+    }
+
+    #[test]
+    fn bool_flag_test() {
+        type Codec = FlagPrefixed<bool, u8>;
+
+        let mut bytes = BytesMut::new();
+
+        Codec::encode(&Some(0x05u8), &mut bytes).unwrap();
+        Codec::encode(&None::<u8>, &mut bytes).unwrap();
+
+        assert_eq!(&bytes[..], &[0x01, 0x05, 0x00]);
+        assert_eq!(Codec::size_of(&Some(0x05u8)), 2);
+        assert_eq!(Codec::decode(&mut bytes).unwrap(), Some(0x05u8));
+        assert_eq!(Codec::decode(&mut bytes).unwrap(), None::<u8>);
+
+        let mut bytes = BytesMut::from_iter([0x02, 0x05, 0x02]);
+
+        assert_eq!(
+            <FlagPrefixed<LenientBool, u8> as Decoder<Option<u8>>>::decode(
+                &mut bytes
+            )
+            .unwrap(),
+            Some(0x05)
+        );
+        assert!(matches!(
+            <Codec as Decoder<Option<u8>>>::decode(&mut bytes),
+            Err(Error::InvalidBool(2))
+        ));
+    }
+
+    #[test]
+    fn optional_invalid_test() {
+        let mut bytes = BytesMut::from_iter([0x02, 0x00]);
+
+        assert!(matches!(
+            <FlagPrefixed<u8, u8> as Decoder<Option<u8>>>::decode(&mut bytes),
+            Err(Error::InvalidFlag(2))
+        ));
+        assert!(matches!(
+            Sentinel::<u8, 0xFF>::encode(&Some(0xFFu8), &mut bytes),
+            Err(Error::ReservedValue)
+        ));
+        assert!(matches!(
+            Sentinel::<u8, 0x100>::encode(&None::<u8>, &mut bytes),
+            Err(Error::IntConversion(_))
+        ));
+    }
+}
@@ -0,0 +1,175 @@
+use std::marker::PhantomData;
+
+use crate::{
+    bytes::{Buf, BufMut},
+    util::BufExt,
+    Decoder,
+    Encoder,
+    Error,
+    FixedSize,
+};
+
+/// An encoder/decoder for values stored in a fixed-width field of `N` bytes.
+///
+/// Values are encoded with `C`, then padded to `N` bytes with `FILL`. Encoding
+/// fails with [`Error::LengthExceeded`] if the value is longer than `N` bytes.
+///
+/// When decoding, the value is decoded with `C` from the `N`-byte field, and
+/// the bytes following it must all equal `FILL`, or decoding fails with
+/// [`Error::InvalidPadding`]. If `STRIP` is `true`, trailing `FILL` bytes are
+/// instead stripped from the field before decoding (e.g. for zero-padded text
+/// with `Padded<16, Unprefixed, 0, true>`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Padded<
+    const N: usize,
+    C,
+    const FILL: u8 = 0,
+    const STRIP: bool = false,
+>(PhantomData<C>);
+
+impl<const N: usize, C, T, const FILL: u8, const STRIP: bool> Decoder<T>
+    for Padded<N, C, FILL, STRIP>
+where
+    C: Decoder<T>,
+    Error: From<<C as Decoder<T>>::Error>,
+{
+    type Error = Error;
+
+    const MAX_SIZE: Option<usize> = Some(N);
+    const MIN_SIZE: usize = N;
+
+    fn decode<B: Buf>(buf: &mut B) -> Result<T, Self::Error> {
+        buf.require_n(N)?;
+
+        let mut field = buf.copy_to_bytes(N);
+
+        if STRIP {
+            let len =
+                field.iter().rposition(|&b| b != FILL).map_or(0, |i| i + 1);
+
+            field.truncate(len);
+        }
+
+        let len = field.len();
+        let value = C::decode(&mut field)?;
+
+        if let Some(i) = field.iter().position(|&b| b != FILL) {
+            return Err(Error::InvalidPadding {
+                offset: len - field.remaining() + i,
+            });
+        }
+
+        Ok(value)
+    }
+
+    #[inline]
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        buf.remaining() >= N
+    }
+}
+
+impl<const N: usize, C, T, const FILL: u8, const STRIP: bool> Encoder<T>
+    for Padded<N, C, FILL, STRIP>
+where
+    T: ?Sized,
+    C: Encoder<T>,
+    Error: From<<C as Encoder<T>>::Error>,
+{
+    type Error = Error;
+
+    const MAX_SIZE: Option<usize> = Some(N);
+    const MIN_SIZE: usize = N;
+
+    fn encode<B: BufMut>(item: &T, buf: &mut B) -> Result<(), Self::Error> {
+        let len = C::size_of(item);
+
+        if len > N {
+            return Err(Error::LengthExceeded { len, max: N });
+        }
+
+        C::encode(item, buf)?;
+        buf.put_bytes(FILL, N - len);
+
+        Ok(())
+    }
+
+    #[inline]
+    fn size_of(_item: &T) -> usize {
+        N
+    }
+}
+
+impl<const N: usize, C, const FILL: u8, const STRIP: bool> FixedSize
+    for Padded<N, C, FILL, STRIP>
+{
+    const SIZE: usize = N;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+
+    use super::*;
+    use crate::codec::{NulTerminated, Unprefixed};
+
+    #[test]
+    fn padded_test() {
+        type Codec = Padded<8, u16, 0xFF>;
+
+        let mut bytes = BytesMut::new();
+
+        Codec::encode(&0x0102u16, &mut bytes).unwrap();
+
+        assert_eq!(Codec::size_of(&0x0102u16), 8);
+        assert_eq!(
+            &bytes[..],
+            &[0x01, 0x02, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+        let decoded: u16 = Codec::decode(&mut bytes.clone()).unwrap();
+
+        assert_eq!(decoded, 0x0102);
+
+        bytes[5] = 0x00;
+
+        assert!(matches!(
+            <Codec as Decoder<u16>>::decode(&mut bytes),
+            Err(Error::InvalidPadding { offset: 5 })
+        ));
+    }
+
+    #[test]
+    fn padded_text_test() {
+        type Stripped = Padded<8, Unprefixed, 0, true>;
+        type CString = Padded<8, NulTerminated>;
+
+        let mut bytes = BytesMut::new();
+
+        Stripped::encode(&"abc", &mut bytes).unwrap();
+        CString::encode("abc", &mut bytes).unwrap();
+
+        assert_eq!(&bytes[..8], b"abc\0\0\0\0\0");
+        assert_eq!(&bytes[8..], b"abc\0\0\0\0\0");
+
+        let stripped: Bytes = Stripped::decode(&mut bytes).unwrap();
+        let cstring: String = CString::decode(&mut bytes).unwrap();
+
+        assert_eq!(&stripped[..], b"abc");
+        assert_eq!(cstring, "abc");
+        assert!(matches!(
+            Stripped::encode(&"abcdefghi", &mut bytes),
+            Err(Error::LengthExceeded { len: 9, max: 8 })
+        ));
+    }
+
+    #[test]
+    fn padded_strip_invalid_test() {
+        type Codec = Padded<8, u16, 0, true>;
+
+        let mut bytes = BytesMut::from_iter([0x01, 0x02, 0x03, 0, 0, 0, 0, 0]);
+
+        assert!(matches!(
+            <Codec as Decoder<u16>>::decode(&mut bytes),
+            Err(Error::InvalidPadding { offset: 2 })
+        ));
+    }
+}
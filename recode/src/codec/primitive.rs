@@ -0,0 +1,524 @@
+use std::marker::PhantomData;
+
+use super::{number::TryFromIntError, BigEndian, LittleEndian};
+use crate::{
+    bytes::{Buf, BufMut},
+    util::BufExt,
+    Decoder,
+    Encoder,
+    Error,
+    FixedSize,
+    RawDecoder,
+};
+
+/// An encoder/decoder for `bool` values that decodes any non-zero byte as
+/// `true`.
+///
+/// `bool` itself is strict, and fails decoding bytes other than `0` or `1`
+/// with [`Error::InvalidBool`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LenientBool;
+
+/// An encoder/decoder for `char` values encoded as UTF-8 (1 to 4 bytes).
+///
+/// `char` itself is encoded as a 4-byte code point.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Utf8Char;
+
+impl Decoder for bool {
+    type Error = Error;
+
+    const MAX_SIZE: Option<usize> = Some(1);
+    const MIN_SIZE: usize = 1;
+
+    #[inline]
+    fn decode<B: Buf>(buf: &mut B) -> Result<bool, Self::Error> {
+        buf.decode_fixed::<Self, bool>(1)
+    }
+
+    #[inline]
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        buf.has_remaining()
+    }
+}
+
+impl RawDecoder for bool {
+    type Error = Error;
+
+    fn raw_decode<'a>(buf: &'a [u8]) -> Result<(bool, usize), Self::Error>
+    where
+        bool: 'a,
+    {
+        buf.require_n(1)?;
+
+        match buf[0] {
+            | 0 => Ok((false, 1)),
+            | 1 => Ok((true, 1)),
+            | b => Err(Error::InvalidBool(b)),
+        }
+    }
+}
+
+impl Encoder for bool {
+    type Error = std::convert::Infallible;
+
+    const MAX_SIZE: Option<usize> = Some(1);
+    const MIN_SIZE: usize = 1;
+
+    #[inline]
+    fn encode<B: BufMut>(item: &bool, buf: &mut B) -> Result<(), Self::Error> {
+        buf.put_u8(u8::from(*item));
+
+        Ok(())
+    }
+
+    #[inline]
+    fn size_of(_: &bool) -> usize {
+        1
+    }
+}
+
+impl FixedSize for bool {
+    const SIZE: usize = 1;
+}
+
+impl Decoder<bool> for LenientBool {
+    type Error = Error;
+
+    const MAX_SIZE: Option<usize> = Some(1);
+    const MIN_SIZE: usize = 1;
+
+    #[inline]
+    fn decode<B: Buf>(buf: &mut B) -> Result<bool, Self::Error> {
+        buf.decode_fixed::<Self, bool>(1)
+    }
+
+    #[inline]
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        buf.has_remaining()
+    }
+}
+
+impl RawDecoder<bool> for LenientBool {
+    type Error = Error;
+
+    #[inline]
+    fn raw_decode<'a>(buf: &'a [u8]) -> Result<(bool, usize), Self::Error>
+    where
+        bool: 'a,
+    {
+        buf.require_n(1)?;
+
+        Ok((buf[0] != 0, 1))
+    }
+}
+
+impl Encoder<bool> for LenientBool {
+    type Error = std::convert::Infallible;
+
+    const MAX_SIZE: Option<usize> = Some(1);
+    const MIN_SIZE: usize = 1;
+
+    #[inline]
+    fn encode<B: BufMut>(item: &bool, buf: &mut B) -> Result<(), Self::Error> {
+        bool::encode(item, buf)
+    }
+
+    #[inline]
+    fn size_of(item: &bool) -> usize {
+        bool::size_of(item)
+    }
+}
+
+impl FixedSize for LenientBool {
+    const SIZE: usize = 1;
+}
+
+/// Implements decoding/encoding `0` and `1` as `usize` values (e.g. presence
+/// flags for [`FlagPrefixed`](super::FlagPrefixed)) with a `bool` codec.
+macro_rules! impl_bool_flag {
+    ($c:ty) => {
+        impl Decoder<usize> for $c {
+            type Error = Error;
+
+            const MAX_SIZE: Option<usize> = Some(1);
+            const MIN_SIZE: usize = 1;
+
+            #[inline]
+            fn decode<B: Buf>(buf: &mut B) -> Result<usize, Self::Error> {
+                <Self as Decoder<bool>>::decode(buf).map(usize::from)
+            }
+
+            #[inline]
+            fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+                buf.has_remaining()
+            }
+        }
+
+        impl Encoder<usize> for $c {
+            type Error = Error;
+
+            const MAX_SIZE: Option<usize> = Some(1);
+            const MIN_SIZE: usize = 1;
+
+            #[inline]
+            fn encode<B: BufMut>(
+                item: &usize,
+                buf: &mut B,
+            ) -> Result<(), Self::Error> {
+                let value = match *item {
+                    | 0 => false,
+                    | 1 => true,
+                    | _ => return Err(TryFromIntError(()).into()),
+                };
+
+                buf.put_u8(u8::from(value));
+
+                Ok(())
+            }
+
+            #[inline]
+            fn size_of(_item: &usize) -> usize {
+                1
+            }
+        }
+    };
+}
+
+impl_bool_flag!(bool);
+impl_bool_flag!(LenientBool);
+
+macro_rules! impl_char {
+    ($c:ty; repr: $r:ty) => {
+        impl Decoder<char> for $c {
+            type Error = Error;
+
+            const MAX_SIZE: Option<usize> = <$r as Decoder<u32>>::MAX_SIZE;
+            const MIN_SIZE: usize = <$r as Decoder<u32>>::MIN_SIZE;
+
+            #[inline]
+            fn decode<B: Buf>(buf: &mut B) -> Result<char, Self::Error> {
+                buf.decode_fixed::<Self, char>(std::mem::size_of::<u32>())
+            }
+
+            #[inline]
+            fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+                <$r as Decoder<u32>>::has_enough_bytes(buf)
+            }
+        }
+
+        impl RawDecoder<char> for $c {
+            type Error = Error;
+
+            #[inline]
+            fn raw_decode<'a>(
+                buf: &'a [u8],
+            ) -> Result<(char, usize), Self::Error>
+            where
+                char: 'a,
+            {
+                let (code, rx) = <$r as RawDecoder<u32>>::raw_decode(buf)?;
+                let value =
+                    char::from_u32(code).ok_or(Error::InvalidChar(code))?;
+
+                Ok((value, rx))
+            }
+        }
+
+        impl Encoder<char> for $c {
+            type Error = std::convert::Infallible;
+
+            const MAX_SIZE: Option<usize> = <$r as Encoder<u32>>::MAX_SIZE;
+            const MIN_SIZE: usize = <$r as Encoder<u32>>::MIN_SIZE;
+
+            #[inline]
+            fn encode<B: BufMut>(
+                item: &char,
+                buf: &mut B,
+            ) -> Result<(), Self::Error> {
+                <$r as Encoder<u32>>::encode(&u32::from(*item), buf)
+            }
+
+            #[inline]
+            fn size_of(_: &char) -> usize {
+                std::mem::size_of::<u32>()
+            }
+        }
+
+        impl FixedSize for $c {
+            const SIZE: usize = <$r as FixedSize>::SIZE;
+        }
+    };
+}
+
+impl_char!(char; repr: u32);
+impl_char!(BigEndian<char>; repr: BigEndian<u32>);
+impl_char!(LittleEndian<char>; repr: LittleEndian<u32>);
+
+impl Utf8Char {
+    /// Returns the length of the UTF-8 sequence starting with `first`, or
+    /// `None` if `first` cannot start a sequence.
+    #[inline]
+    fn width(first: u8) -> Option<usize> {
+        match first {
+            | 0x00..=0x7F => Some(1),
+            | 0xC0..=0xDF => Some(2),
+            | 0xE0..=0xEF => Some(3),
+            | 0xF0..=0xF7 => Some(4),
+            | _ => None,
+        }
+    }
+}
+
+impl Decoder<char> for Utf8Char {
+    type Error = Error;
+
+    const MAX_SIZE: Option<usize> = Some(4);
+    const MIN_SIZE: usize = 1;
+
+    #[inline]
+    fn decode<B: Buf>(buf: &mut B) -> Result<char, Self::Error> {
+        // invalid first bytes are reported by `raw_decode`
+        let width = buf
+            .chunk()
+            .first()
+            .and_then(|&b| Self::width(b))
+            .unwrap_or(1);
+
+        buf.decode_fixed::<Self, char>(width)
+    }
+
+    #[inline]
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        buf.chunk().first().is_some_and(|&b| {
+            Self::width(b).is_none_or(|width| buf.remaining() >= width)
+        })
+    }
+}
+
+impl RawDecoder<char> for Utf8Char {
+    type Error = Error;
+
+    fn raw_decode<'a>(buf: &'a [u8]) -> Result<(char, usize), Self::Error>
+    where
+        char: 'a,
+    {
+        buf.require_n(1)?;
+
+        let width =
+            Self::width(buf[0]).ok_or(Error::InvalidUtf8 { offset: 0 })?;
+
+        buf.require_n(width)?;
+
+        let value = std::str::from_utf8(&buf[..width])
+            .map_err(|e| Error::InvalidUtf8 {
+                offset: e.valid_up_to(),
+            })?
+            .chars()
+            .next()
+            .expect("a valid utf-8 sequence of at least one byte");
+
+        Ok((value, width))
+    }
+}
+
+impl Encoder<char> for Utf8Char {
+    type Error = std::convert::Infallible;
+
+    const MAX_SIZE: Option<usize> = Some(4);
+    const MIN_SIZE: usize = 1;
+
+    #[inline]
+    fn encode<B: BufMut>(item: &char, buf: &mut B) -> Result<(), Self::Error> {
+        buf.put_slice(item.encode_utf8(&mut [0; 4]).as_bytes());
+
+        Ok(())
+    }
+
+    #[inline]
+    fn size_of(item: &char) -> usize {
+        item.len_utf8()
+    }
+}
+
+impl Decoder for () {
+    type Error = std::convert::Infallible;
+
+    const MAX_SIZE: Option<usize> = Some(0);
+    const MIN_SIZE: usize = 0;
+
+    #[inline]
+    fn decode<B: Buf>(_buf: &mut B) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl RawDecoder for () {
+    type Error = std::convert::Infallible;
+
+    #[inline]
+    fn raw_decode<'a>(_buf: &'a [u8]) -> Result<((), usize), Self::Error>
+    where
+        (): 'a,
+    {
+        Ok(((), 0))
+    }
+}
+
+impl Encoder for () {
+    type Error = std::convert::Infallible;
+
+    const MAX_SIZE: Option<usize> = Some(0);
+    const MIN_SIZE: usize = 0;
+
+    #[inline]
+    fn encode<B: BufMut>(_item: &(), _buf: &mut B) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    #[inline]
+    fn size_of(_item: &()) -> usize {
+        0
+    }
+}
+
+impl FixedSize for () {
+    const SIZE: usize = 0;
+}
+
+impl<T> Decoder for PhantomData<T>
+where
+    T: ?Sized,
+{
+    type Error = std::convert::Infallible;
+
+    const MAX_SIZE: Option<usize> = Some(0);
+    const MIN_SIZE: usize = 0;
+
+    #[inline]
+    fn decode<B: Buf>(_buf: &mut B) -> Result<PhantomData<T>, Self::Error> {
+        Ok(PhantomData)
+    }
+}
+
+impl<T> RawDecoder for PhantomData<T>
+where
+    T: ?Sized,
+{
+    type Error = std::convert::Infallible;
+
+    #[inline]
+    fn raw_decode<'a>(
+        _buf: &'a [u8],
+    ) -> Result<(PhantomData<T>, usize), Self::Error>
+    where
+        PhantomData<T>: 'a,
+    {
+        Ok((PhantomData, 0))
+    }
+}
+
+impl<T> Encoder for PhantomData<T>
+where
+    T: ?Sized,
+{
+    type Error = std::convert::Infallible;
+
+    const MAX_SIZE: Option<usize> = Some(0);
+    const MIN_SIZE: usize = 0;
+
+    #[inline]
+    fn encode<B: BufMut>(
+        _item: &PhantomData<T>,
+        _buf: &mut B,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    #[inline]
+    fn size_of(_item: &PhantomData<T>) -> usize {
+        0
+    }
+}
+
+impl<T> FixedSize for PhantomData<T> {
+    const SIZE: usize = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use bytes::BytesMut;
+
+    use super::*;
+    use crate as recode;
+    use crate::{codec::Le, util::EncoderExt, Recode};
+
+    #[derive(Debug, PartialEq, Eq, Recode)]
+    #[recode(error = "crate::Error")]
+    struct Flags<T> {
+        enabled: bool,
+        #[recode(with = "LenientBool")]
+        visible: bool,
+        initial: char,
+        #[recode(with = "Utf8Char")]
+        symbol: char,
+        #[recode(with = "Le<char>")]
+        suffix: char,
+        unit: (),
+        marker: PhantomData<T>,
+    }
+
+    #[test]
+    fn primitive_struct_test() {
+        let flags = Flags::<String> {
+            enabled: true,
+            visible: true,
+            initial: 'a',
+            symbol: '€',
+            suffix: 'z',
+            unit: (),
+            marker: PhantomData,
+        };
+        let mut bytes = BytesMut::new();
+
+        flags.encode_to(&mut bytes).unwrap();
+
+        assert_eq!(
+            &bytes[..],
+            &[
+                0x01, 0x01, 0x00, 0x00, 0x00, b'a', 0xE2, 0x82, 0xAC, b'z',
+                0x00, 0x00, 0x00
+            ]
+        );
+        assert_eq!(flags.size(), bytes.len());
+
+        bytes[1] = 0x02;
+
+        assert_eq!(Flags::decode(&mut bytes).unwrap(), flags);
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn primitive_invalid_test() {
+        let mut bytes = BytesMut::from_iter([0x02, 0x00, 0x00, 0xD8, 0x00]);
+
+        assert!(matches!(
+            <bool as Decoder<bool>>::decode(&mut bytes),
+            Err(Error::InvalidBool(2))
+        ));
+        assert!(matches!(
+            char::decode(&mut bytes.split_off(1)),
+            Err(Error::InvalidChar(0xD800))
+        ));
+        assert!(matches!(
+            Utf8Char::decode(&mut BytesMut::from(&b"\xE2\x82"[..])),
+            Err(Error::BytesNeeded { needed: 1, .. })
+        ));
+        assert!(matches!(
+            Utf8Char::decode(&mut BytesMut::from(&b"\xFF"[..])),
+            Err(Error::InvalidUtf8 { offset: 0 })
+        ));
+    }
+}
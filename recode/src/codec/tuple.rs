@@ -0,0 +1,98 @@
+use crate::{
+    bytes::{Buf, BufMut},
+    Decoder,
+    Encoder,
+    Error,
+    FixedSize,
+};
+
+macro_rules! impl_tuple {
+    ($($c:ident: $t:ident),+) => {
+        impl<$($c, $t),+> Decoder<($($t,)+)> for ($($c,)+)
+        where
+            $($c: Decoder<$t>,)+
+            $(Error: From<<$c as Decoder<$t>>::Error>,)+
+        {
+            type Error = Error;
+
+            const MIN_SIZE: usize = 0 $(+ <$c as Decoder<$t>>::MIN_SIZE)+;
+            const MAX_SIZE: Option<usize> = crate::util::size::sum_max(&[$(<$c as Decoder<$t>>::MAX_SIZE),+]);
+
+            #[inline]
+            fn decode<Buffer: Buf>(
+                buf: &mut Buffer,
+            ) -> Result<($($t,)+), Self::Error> {
+                Ok(($($c::decode(buf)?,)+))
+            }
+        }
+
+        impl<$($c, $t),+> Encoder<($($t,)+)> for ($($c,)+)
+        where
+            $($c: Encoder<$t>,)+
+            $(Error: From<<$c as Encoder<$t>>::Error>,)+
+        {
+            type Error = Error;
+
+            const MIN_SIZE: usize = 0 $(+ <$c as Encoder<$t>>::MIN_SIZE)+;
+            const MAX_SIZE: Option<usize> = crate::util::size::sum_max(&[$(<$c as Encoder<$t>>::MAX_SIZE),+]);
+
+            #[inline]
+            #[allow(non_snake_case)]
+            fn encode<Buffer: BufMut>(
+                item: &($($t,)+),
+                buf: &mut Buffer,
+            ) -> Result<(), Self::Error> {
+                let ($($t,)+) = item;
+
+                $($c::encode($t, buf)?;)+
+
+                Ok(())
+            }
+
+            #[inline]
+            #[allow(non_snake_case)]
+            fn size_of(item: &($($t,)+)) -> usize {
+                let ($($t,)+) = item;
+
+                0 $(+ $c::size_of($t))+
+            }
+        }
+
+        impl<$($c),+> FixedSize for ($($c,)+)
+        where
+            $($c: FixedSize,)+
+        {
+            const SIZE: usize = 0 $(+ $c::SIZE)+;
+        }
+    };
+}
+
+impl_tuple!(A: TA);
+impl_tuple!(A: TA, B: TB);
+impl_tuple!(A: TA, B: TB, C: TC);
+impl_tuple!(A: TA, B: TB, C: TC, D: TD);
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+
+    use crate::{codec::LengthPrefixed, Decoder, Encoder};
+
+    #[test]
+    fn pair_test() {
+        type Codec = (u8, LengthPrefixed<u16>);
+
+        let item = (0x01u8, Bytes::from_static(b"abc"));
+        let mut bytes = BytesMut::new();
+
+        assert_eq!(Codec::size_of(&item), 1 + 2 + 3);
+
+        Codec::encode(&item, &mut bytes).unwrap();
+
+        assert_eq!(&bytes[..], &[0x01, 0x00, 0x03, b'a', b'b', b'c']);
+
+        let decoded: (u8, Bytes) = Codec::decode(&mut bytes).unwrap();
+
+        assert_eq!(decoded, item);
+    }
+}
@@ -1,33 +1,43 @@
 pub use ux::{i24, i40, i48, i56, u24, u40, u48, u56};
 
+use super::{BigEndian, LittleEndian};
 use crate::{
-    bytes::{Buf, BufMut, BytesMut},
-    util::EncoderExt,
+    bytes::{Buf, BufMut},
+    util::BufExt,
     Decoder,
     Encoder,
+    FixedSize,
     RawDecoder,
 };
 
 macro_rules! impl_ux {
-    ($t:ty; size: $s:literal; rep: $r:ty) => {
-        impl Decoder for $t {
+    (
+        $t:ty;
+        size: $s:literal;
+        rep: $r:ty;
+        codec: $c:ty;
+        from: $from:ident;
+        to: $to:ident;
+        range: $range:expr
+    ) => {
+        impl Decoder<$t> for $c {
             type Error = crate::Error;
 
-            #[inline]
-            fn decode(buf: &mut BytesMut) -> Result<Self, Self::Error> {
-                let (num, off) = Self::raw_decode(buf)?;
-                buf.advance(off);
+            const MIN_SIZE: usize = $s;
+            const MAX_SIZE: Option<usize> = Some($s);
 
-                Ok(num)
+            #[inline]
+            fn decode<B: Buf>(buf: &mut B) -> Result<$t, Self::Error> {
+                buf.decode_fixed::<Self, $t>($s)
             }
 
             #[inline]
-            fn has_enough_bytes(buf: &BytesMut) -> bool {
+            fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
                 buf.remaining() >= $s
             }
         }
 
-        impl RawDecoder for $t {
+        impl RawDecoder<$t> for $c {
             type Error = crate::Error;
 
             fn raw_decode<'a>(buf: &'a [u8]) -> Result<($t, usize), Self::Error>
@@ -44,23 +54,21 @@ macro_rules! impl_ux {
                     });
                 }
 
-                let mut be_repr = [0u8; REPR_LEN];
-                be_repr[(REPR_LEN - $s)..].copy_from_slice(&buf[..$s]);
+                let mut repr = [0u8; REPR_LEN];
+                repr[$range].copy_from_slice(&buf[..$s]);
 
-                Ok((<$t>::new(<$r>::from_be_bytes(be_repr)), $s))
+                Ok((<$t>::new(<$r>::$from(repr)), $s))
             }
         }
 
-        impl Encoder for $t {
+        impl Encoder<$t> for $c {
             type Error = std::convert::Infallible;
 
-            fn encode(
-                item: &$t,
-                buf: &mut BytesMut,
-            ) -> Result<(), Self::Error> {
-                const REPR_LEN: usize = std::mem::size_of::<$r>();
+            const MIN_SIZE: usize = $s;
+            const MAX_SIZE: Option<usize> = Some($s);
 
-                let bytes = &<$r>::from(*item).to_be_bytes()[(REPR_LEN - $s)..];
+            fn encode<B: BufMut>(item: &$t, buf: &mut B) -> Result<(), Self::Error> {
+                let bytes = &<$r>::from(*item).$to()[$range];
 
                 buf.put_slice(bytes);
 
@@ -73,11 +81,14 @@ macro_rules! impl_ux {
             }
         }
 
-        impl Decoder<usize> for $t {
+        impl Decoder<usize> for $c {
             type Error = crate::Error;
 
-            fn decode(buf: &mut bytes::BytesMut) -> Result<usize, Self::Error> {
-                let value = <Self as Decoder>::decode(buf)?;
+            const MIN_SIZE: usize = <Self as Decoder<$t>>::MIN_SIZE;
+            const MAX_SIZE: Option<usize> = <Self as Decoder<$t>>::MAX_SIZE;
+
+            fn decode<B: Buf>(buf: &mut B) -> Result<usize, Self::Error> {
+                let value = <Self as Decoder<$t>>::decode(buf)?;
 
                 usize::try_from(<$r>::from(value))
                     .map_err(|_| super::number::TryFromIntError(()))
@@ -85,12 +96,12 @@ macro_rules! impl_ux {
             }
 
             #[inline]
-            fn has_enough_bytes(buf: &BytesMut) -> bool {
-                <Self as Decoder>::has_enough_bytes(buf)
+            fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+                <Self as Decoder<$t>>::has_enough_bytes(buf)
             }
         }
 
-        impl RawDecoder<usize> for $t {
+        impl RawDecoder<usize> for $c {
             type Error = crate::Error;
 
             #[inline]
@@ -100,7 +111,7 @@ macro_rules! impl_ux {
             where
                 $t: 'a,
             {
-                let (value, rx) = <Self as RawDecoder>::raw_decode(buf)?;
+                let (value, rx) = <Self as RawDecoder<$t>>::raw_decode(buf)?;
                 let value: usize = <$r>::from(value)
                     .try_into()
                     .map_err(|_| super::number::TryFromIntError(()))?;
@@ -109,17 +120,18 @@ macro_rules! impl_ux {
             }
         }
 
-        impl Encoder<usize> for $t {
+        impl Encoder<usize> for $c {
             type Error = crate::Error;
 
-            fn encode(
-                item: &usize,
-                buf: &mut BytesMut,
-            ) -> Result<(), Self::Error> {
+            const MIN_SIZE: usize = <Self as Encoder<$t>>::MIN_SIZE;
+            const MAX_SIZE: Option<usize> = <Self as Encoder<$t>>::MAX_SIZE;
+
+            fn encode<B: BufMut>(item: &usize, buf: &mut B) -> Result<(), Self::Error> {
                 let value = <$r>::try_from(*item)
                     .map_err(|_| super::number::TryFromIntError(()))?;
 
-                <$t>::new(value).encode_to(buf).map_err(Into::into)
+                <Self as Encoder<$t>>::encode(&<$t>::new(value), buf)
+                    .map_err(Into::into)
             }
 
             #[inline]
@@ -127,6 +139,39 @@ macro_rules! impl_ux {
                 $s
             }
         }
+
+        impl FixedSize for $c {
+            const SIZE: usize = $s;
+        }
+    };
+    ($t:ty; size: $s:literal; rep: $r:ty) => {
+        impl_ux!(
+            $t;
+            size: $s;
+            rep: $r;
+            codec: $t;
+            from: from_be_bytes;
+            to: to_be_bytes;
+            range: (std::mem::size_of::<$r>() - $s)..
+        );
+        impl_ux!(
+            $t;
+            size: $s;
+            rep: $r;
+            codec: BigEndian<$t>;
+            from: from_be_bytes;
+            to: to_be_bytes;
+            range: (std::mem::size_of::<$r>() - $s)..
+        );
+        impl_ux!(
+            $t;
+            size: $s;
+            rep: $r;
+            codec: LittleEndian<$t>;
+            from: from_le_bytes;
+            to: to_le_bytes;
+            range: ..$s
+        );
     };
 }
 
@@ -189,6 +234,27 @@ mod tests {
                     assert_eq!(decoded, value);
                     assert!(!bytes.has_remaining());
                 }
+
+                #[test]
+                fn [<test_le_ $t>]() {
+                    let rmax: $r = <$t>::MAX.into();
+                    let repr: $r = (0..rmax).fake();
+
+                    let value = <$t>::new(repr);
+                    let mut bytes = BytesMut::new();
+
+                    assert_eq!($s, LittleEndian::<$t>::size_of(&value));
+
+                    LittleEndian::<$t>::encode(&value, &mut bytes).unwrap();
+
+                    assert_eq!(&repr.to_le_bytes()[..$s], &bytes[..]);
+
+                    let decoded: $t =
+                        LittleEndian::<$t>::decode(&mut bytes).unwrap();
+
+                    assert_eq!(decoded, value);
+                    assert!(!bytes.has_remaining());
+                }
             }
         };
     }
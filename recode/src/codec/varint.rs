@@ -0,0 +1,682 @@
+use std::marker::PhantomData;
+
+use super::TryFromIntError;
+use crate::{
+    bytes::{Buf, BufMut},
+    util::BufExt,
+    Decoder,
+    Encoder,
+    Error,
+    RawDecoder,
+};
+
+/// An encoder/decoder for unsigned [LEB128](https://en.wikipedia.org/wiki/LEB128)
+/// integers, as used by protobuf, WebAssembly and DWARF.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Uleb128;
+
+/// An encoder/decoder for signed [LEB128](https://en.wikipedia.org/wiki/LEB128)
+/// integers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sleb128;
+
+/// An encoder/decoder for signed integers that are
+/// [zigzag](https://protobuf.dev/programming-guides/encoding/#signed-ints)-mapped
+/// to unsigned integers before being encoded with `C`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZigZag<C = Uleb128>(PhantomData<C>);
+
+/// An encoder/decoder for QUIC variable-length integers (RFC 9000 §16),
+/// where the two most significant bits of the first byte encode the length.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QuicVarInt;
+
+/// An encoder/decoder for MQTT variable byte integers, which are at most 4
+/// bytes long.
+///
+/// Decoding fails with [`Error::VarIntOverlong`] if the value is not encoded
+/// in the minimum number of bytes, as the specification requires.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MqttVarInt;
+
+impl QuicVarInt {
+    /// The maximum value that can be encoded as a QUIC variable-length
+    /// integer.
+    pub const MAX: u64 = (1 << 62) - 1;
+}
+
+impl MqttVarInt {
+    /// The maximum value that can be encoded as an MQTT variable byte integer.
+    pub const MAX: u32 = (1 << 28) - 1;
+}
+
+/// Returns a [`Error::BytesNeeded`] error for a variable-length integer whose
+/// full length is not known yet.
+#[inline]
+fn more_bytes_needed(available: usize) -> Error {
+    Error::BytesNeeded {
+        needed: 1,
+        full_len: available + 1,
+        available,
+    }
+}
+
+impl RawDecoder<u64> for Uleb128 {
+    type Error = Error;
+
+    fn raw_decode<'a>(buf: &'a [u8]) -> Result<(u64, usize), Self::Error>
+    where
+        u64: 'a,
+    {
+        const MAX_LEN: usize = 10;
+
+        let mut value = 0u64;
+
+        for (i, &byte) in buf.iter().enumerate() {
+            let bits = u64::from(byte & 0x7F);
+
+            // the 10th byte can only hold the most significant bit
+            if i == MAX_LEN - 1 && bits > 1 {
+                return Err(Error::VarIntOverflow);
+            }
+
+            value |= bits << (7 * i);
+
+            if byte & 0x80 == 0 {
+                return Ok((value, i + 1));
+            }
+
+            if i == MAX_LEN - 1 {
+                return Err(Error::VarIntOverlong { max: MAX_LEN });
+            }
+        }
+
+        Err(more_bytes_needed(buf.len()))
+    }
+}
+
+impl Encoder<u64> for Uleb128 {
+    type Error = Error;
+
+    fn encode<B: BufMut>(item: &u64, buf: &mut B) -> Result<(), Self::Error> {
+        let mut value = *item;
+
+        while value >= 0x80 {
+            buf.put_u8((value as u8) | 0x80);
+            value >>= 7;
+        }
+
+        buf.put_u8(value as u8);
+
+        Ok(())
+    }
+
+    #[inline]
+    fn size_of(item: &u64) -> usize {
+        std::cmp::max(1, (64 - item.leading_zeros() as usize).div_ceil(7))
+    }
+}
+
+impl RawDecoder<i64> for Sleb128 {
+    type Error = Error;
+
+    fn raw_decode<'a>(buf: &'a [u8]) -> Result<(i64, usize), Self::Error>
+    where
+        i64: 'a,
+    {
+        const MAX_LEN: usize = 10;
+
+        let mut value = 0i64;
+
+        for (i, &byte) in buf.iter().enumerate() {
+            let shift = 7 * i;
+
+            // the 10th byte can only hold the sign bit and its extension
+            if i == MAX_LEN - 1 && !matches!(byte & 0x7F, 0x00 | 0x7F) {
+                return Err(Error::VarIntOverflow);
+            }
+
+            value |= i64::from(byte & 0x7F) << shift;
+
+            if byte & 0x80 == 0 {
+                if shift + 7 < 64 && byte & 0x40 != 0 {
+                    value |= -1 << (shift + 7);
+                }
+
+                return Ok((value, i + 1));
+            }
+
+            if i == MAX_LEN - 1 {
+                return Err(Error::VarIntOverlong { max: MAX_LEN });
+            }
+        }
+
+        Err(more_bytes_needed(buf.len()))
+    }
+}
+
+impl Encoder<i64> for Sleb128 {
+    type Error = Error;
+
+    fn encode<B: BufMut>(item: &i64, buf: &mut B) -> Result<(), Self::Error> {
+        let mut value = *item;
+
+        loop {
+            let byte = (value as u8) & 0x7F;
+
+            value >>= 7;
+
+            if (value == 0 && byte & 0x40 == 0)
+                || (value == -1 && byte & 0x40 != 0)
+            {
+                buf.put_u8(byte);
+
+                return Ok(());
+            }
+
+            buf.put_u8(byte | 0x80);
+        }
+    }
+
+    #[inline]
+    fn size_of(item: &i64) -> usize {
+        let significant_bits = if *item < 0 {
+            64 - item.leading_ones() as usize
+        } else {
+            64 - item.leading_zeros() as usize
+        };
+
+        // one extra bit is needed for the sign
+        significant_bits / 7 + 1
+    }
+}
+
+impl<C> RawDecoder<i64> for ZigZag<C>
+where
+    C: RawDecoder<u64>,
+    Error: From<<C as RawDecoder<u64>>::Error>,
+{
+    type Error = Error;
+
+    #[inline]
+    fn raw_decode<'a>(buf: &'a [u8]) -> Result<(i64, usize), Self::Error>
+    where
+        i64: 'a,
+    {
+        let (value, rx) = C::raw_decode(buf)?;
+
+        Ok((((value >> 1) as i64) ^ -((value & 1) as i64), rx))
+    }
+}
+
+impl<C> Encoder<i64> for ZigZag<C>
+where
+    C: Encoder<u64>,
+    Error: From<<C as Encoder<u64>>::Error>,
+{
+    type Error = Error;
+
+    #[inline]
+    fn encode<B: BufMut>(item: &i64, buf: &mut B) -> Result<(), Self::Error> {
+        C::encode(&Self::zigzag(*item), buf).map_err(Into::into)
+    }
+
+    #[inline]
+    fn size_of(item: &i64) -> usize {
+        C::size_of(&Self::zigzag(*item))
+    }
+}
+
+impl<C> ZigZag<C> {
+    #[inline]
+    const fn zigzag(value: i64) -> u64 {
+        ((value << 1) ^ (value >> 63)) as u64
+    }
+}
+
+impl RawDecoder<u64> for QuicVarInt {
+    type Error = Error;
+
+    fn raw_decode<'a>(buf: &'a [u8]) -> Result<(u64, usize), Self::Error>
+    where
+        u64: 'a,
+    {
+        let Some(&first) = buf.first() else {
+            return Err(more_bytes_needed(0));
+        };
+
+        let len = 1 << (first >> 6);
+
+        if buf.len() < len {
+            return Err(Error::BytesNeeded {
+                needed: len - buf.len(),
+                full_len: len,
+                available: buf.len(),
+            });
+        }
+
+        let value = buf[1..len]
+            .iter()
+            .fold(u64::from(first & 0x3F), |acc, &b| (acc << 8) | u64::from(b));
+
+        Ok((value, len))
+    }
+}
+
+impl Encoder<u64> for QuicVarInt {
+    type Error = Error;
+
+    fn encode<B: BufMut>(item: &u64, buf: &mut B) -> Result<(), Self::Error> {
+        match Self::size_of(item) {
+            | 1 => buf.put_u8(*item as u8),
+            | 2 => buf.put_u16(0x4000 | *item as u16),
+            | 4 => buf.put_u32(0x8000_0000 | *item as u32),
+            | _ if *item <= Self::MAX => {
+                buf.put_u64(0xC000_0000_0000_0000 | *item)
+            }
+            | _ => return Err(Error::VarIntOverflow),
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn size_of(item: &u64) -> usize {
+        match *item {
+            | 0..=0x3F => 1,
+            | 0x40..=0x3FFF => 2,
+            | 0x4000..=0x3FFF_FFFF => 4,
+            | _ => 8,
+        }
+    }
+}
+
+impl RawDecoder<u32> for MqttVarInt {
+    type Error = Error;
+
+    fn raw_decode<'a>(buf: &'a [u8]) -> Result<(u32, usize), Self::Error>
+    where
+        u32: 'a,
+    {
+        const MAX_LEN: usize = 4;
+
+        let mut value = 0u32;
+
+        for (i, &byte) in buf.iter().enumerate() {
+            value |= u32::from(byte & 0x7F) << (7 * i);
+
+            // a last byte of zero adds nothing to the shorter encoding
+            if byte == 0 && i > 0 {
+                return Err(Error::VarIntOverlong { max: i });
+            }
+
+            if byte & 0x80 == 0 {
+                return Ok((value, i + 1));
+            }
+
+            if i == MAX_LEN - 1 {
+                return Err(Error::VarIntOverlong { max: MAX_LEN });
+            }
+        }
+
+        Err(more_bytes_needed(buf.len()))
+    }
+}
+
+impl Encoder<u32> for MqttVarInt {
+    type Error = Error;
+
+    fn encode<B: BufMut>(item: &u32, buf: &mut B) -> Result<(), Self::Error> {
+        if *item > Self::MAX {
+            return Err(Error::VarIntOverflow);
+        }
+
+        let mut value = *item;
+
+        while value >= 0x80 {
+            buf.put_u8((value as u8) | 0x80);
+            value >>= 7;
+        }
+
+        buf.put_u8(value as u8);
+
+        Ok(())
+    }
+
+    #[inline]
+    fn size_of(item: &u32) -> usize {
+        match *item {
+            | 0..=0x7F => 1,
+            | 0x80..=0x3FFF => 2,
+            | 0x4000..=0x1F_FFFF => 3,
+            | _ => 4,
+        }
+    }
+}
+
+/// The number of bytes a varint is looked for in; this is more than the
+/// longest varint of any supported type.
+const MAX_VARINT_LEN: usize = 16;
+
+/// Decodes a varint with the raw decoder `D`, and advances the buffer past it.
+///
+/// Varints that are not contiguous in `buf` are read byte by byte, so they are
+/// consumed even if they are incomplete or invalid.
+fn decode_varint<D, T, B>(buf: &mut B) -> Result<T, Error>
+where
+    D: RawDecoder<T, Error = Error>,
+    B: Buf,
+{
+    if let Some(bytes) = buf.peek(&mut [0; MAX_VARINT_LEN]) {
+        let (value, rx) = D::raw_decode(bytes)?;
+
+        buf.advance(rx);
+
+        return Ok(value);
+    }
+
+    let mut bytes = [0; MAX_VARINT_LEN];
+    let mut len = 0;
+
+    loop {
+        bytes[len] = buf.get_u8();
+        len += 1;
+
+        match D::raw_decode(&bytes[..len]) {
+            | Err(Error::BytesNeeded { .. })
+                if buf.has_remaining() && len < bytes.len() => {}
+            | result => return result.map(|(value, _)| value),
+        }
+    }
+}
+
+macro_rules! impl_varint {
+    (
+        $(@<$g:ident>)? $c:ty => $t:ty
+        $(where $($w:tt)+)?
+    ) => {
+        impl$(<$g>)? Decoder<$t> for $c
+        $(where $($w)+)?
+        {
+            type Error = Error;
+
+            const MIN_SIZE: usize = 1;
+
+            #[inline]
+            fn decode<B: Buf>(buf: &mut B) -> Result<$t, Self::Error> {
+                decode_varint::<Self, $t, B>(buf)
+            }
+
+            #[inline]
+            fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+                // buffers that do not expose their bytes are left to `decode`
+                buf.peek(&mut [0; MAX_VARINT_LEN]).is_none_or(|bytes| {
+                    !matches!(
+                        <Self as RawDecoder<$t>>::raw_decode(bytes),
+                        Err(Error::BytesNeeded { .. })
+                    )
+                })
+            }
+        }
+
+        impl$(<$g>)? Decoder<usize> for $c
+        $(where $($w)+)?
+        {
+            type Error = Error;
+
+            const MIN_SIZE: usize = 1;
+
+            #[inline]
+            fn decode<B: Buf>(buf: &mut B) -> Result<usize, Self::Error> {
+                usize::try_from(<Self as Decoder<$t>>::decode(buf)?)
+                    .map_err(TryFromIntError::from)
+                    .map_err(Into::into)
+            }
+
+            #[inline]
+            fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+                <Self as Decoder<$t>>::has_enough_bytes(buf)
+            }
+        }
+
+        impl$(<$g>)? RawDecoder<usize> for $c
+        $(where $($w)+)?
+        {
+            type Error = Error;
+
+            #[inline]
+            fn raw_decode<'a>(
+                buf: &'a [u8],
+            ) -> Result<(usize, usize), Self::Error>
+            where
+                usize: 'a,
+            {
+                let (value, rx) = <Self as RawDecoder<$t>>::raw_decode(buf)?;
+                let value = value.try_into().map_err(TryFromIntError::from)?;
+
+                Ok((value, rx))
+            }
+        }
+
+        impl$(<$g>)? Encoder<usize> for $c
+        $(where $($w)+)?
+        {
+            type Error = Error;
+
+            const MIN_SIZE: usize = 1;
+
+            #[inline]
+            fn encode<B: BufMut>(item: &usize, buf: &mut B) -> Result<(), Self::Error> {
+                let value = <$t>::try_from(*item).map_err(TryFromIntError::from)?;
+
+                <Self as Encoder<$t>>::encode(&value, buf)
+            }
+
+            #[inline]
+            fn size_of(item: &usize) -> usize {
+                // values that do not fit are rejected by `encode`, and are
+                // sized as the longest encoding
+                let value = <$t>::try_from(*item).unwrap_or(<$t>::MAX);
+
+                <Self as Encoder<$t>>::size_of(&value)
+            }
+        }
+    };
+}
+
+impl_varint!(Uleb128 => u64);
+impl_varint!(Sleb128 => i64);
+impl_varint!(
+    @<C> ZigZag<C> => i64
+    where
+        C: RawDecoder<u64> + Encoder<u64>,
+        Error: From<<C as RawDecoder<u64>>::Error>
+            + From<<C as Encoder<u64>>::Error>
+);
+impl_varint!(QuicVarInt => u64);
+impl_varint!(MqttVarInt => u32);
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+    use fake::Fake;
+
+    use super::*;
+    use crate::codec::LengthPrefixed;
+
+    macro_rules! test_varint {
+        ($name:ident; $c:ty => $t:ty; $($value:expr => [$($byte:expr),*]),+ $(,)?) => {
+            #[test]
+            fn $name() {
+                $(
+                    let value: $t = $value;
+                    let expected: &[u8] = &[$($byte),*];
+                    let mut bytes = BytesMut::new();
+
+                    assert_eq!(<$c as Encoder<$t>>::size_of(&value), expected.len());
+
+                    <$c as Encoder<$t>>::encode(&value, &mut bytes).unwrap();
+
+                    assert_eq!(&bytes[..], expected);
+
+                    let decoded: $t = <$c as Decoder<$t>>::decode(&mut bytes).unwrap();
+
+                    assert_eq!(decoded, value);
+                    assert!(bytes.is_empty());
+                )+
+            }
+        };
+    }
+
+    test_varint!(
+        uleb128_test; Uleb128 => u64;
+        0 => [0x00],
+        0x7F => [0x7F],
+        0x80 => [0x80, 0x01],
+        624485 => [0xE5, 0x8E, 0x26],
+        u64::MAX => [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01],
+    );
+
+    test_varint!(
+        sleb128_test; Sleb128 => i64;
+        0 => [0x00],
+        -1 => [0x7F],
+        63 => [0x3F],
+        64 => [0xC0, 0x00],
+        -64 => [0x40],
+        -123456 => [0xC0, 0xBB, 0x78],
+        i64::MIN => [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7F],
+        i64::MAX => [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00],
+    );
+
+    test_varint!(
+        zigzag_test; ZigZag => i64;
+        0 => [0x00],
+        -1 => [0x01],
+        1 => [0x02],
+        -2 => [0x03],
+        2147483647 => [0xFE, 0xFF, 0xFF, 0xFF, 0x0F],
+        -2147483648 => [0xFF, 0xFF, 0xFF, 0xFF, 0x0F],
+    );
+
+    test_varint!(
+        quic_test; QuicVarInt => u64;
+        37 => [0x25],
+        15293 => [0x7B, 0xBD],
+        494878333 => [0x9D, 0x7F, 0x3E, 0x7D],
+        151288809941952652 => [0xC2, 0x19, 0x7C, 0x5E, 0xFF, 0x14, 0xE8, 0x8C],
+    );
+
+    test_varint!(
+        mqtt_test; MqttVarInt => u32;
+        0 => [0x00],
+        127 => [0x7F],
+        128 => [0x80, 0x01],
+        16383 => [0xFF, 0x7F],
+        2097152 => [0x80, 0x80, 0x80, 0x01],
+        MqttVarInt::MAX => [0xFF, 0xFF, 0xFF, 0x7F],
+    );
+
+    #[test]
+    fn invalid_varint_test() {
+        let overflow =
+            [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02];
+        let overlong =
+            [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80];
+
+        assert!(matches!(
+            <Uleb128 as RawDecoder<u64>>::raw_decode(&overflow),
+            Err(Error::VarIntOverflow)
+        ));
+        assert!(matches!(
+            <Uleb128 as RawDecoder<u64>>::raw_decode(&overlong[..9]),
+            Err(Error::BytesNeeded { needed: 1, .. })
+        ));
+        assert!(matches!(
+            <Uleb128 as RawDecoder<u64>>::raw_decode(&[
+                0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x81
+            ]),
+            Err(Error::VarIntOverlong { max: 10 })
+        ));
+        assert!(matches!(
+            <Sleb128 as RawDecoder<i64>>::raw_decode(&overflow),
+            Err(Error::VarIntOverflow)
+        ));
+        assert!(matches!(
+            <QuicVarInt as RawDecoder<u64>>::raw_decode(&[0x9D, 0x7F]),
+            Err(Error::BytesNeeded {
+                needed: 2,
+                full_len: 4,
+                available: 2
+            })
+        ));
+        assert!(matches!(
+            <QuicVarInt as Encoder<u64>>::encode(
+                &(QuicVarInt::MAX + 1),
+                &mut BytesMut::new()
+            ),
+            Err(Error::VarIntOverflow)
+        ));
+        assert!(matches!(
+            <MqttVarInt as RawDecoder<u32>>::raw_decode(&overlong),
+            Err(Error::VarIntOverlong { max: 4 })
+        ));
+        assert!(matches!(
+            <MqttVarInt as RawDecoder<u32>>::raw_decode(&[0x80, 0x00]),
+            Err(Error::VarIntOverlong { max: 1 })
+        ));
+        assert!(matches!(
+            <MqttVarInt as Decoder<u32>>::decode(&mut &[0xFF, 0x80, 0x00][..]),
+            Err(Error::VarIntOverlong { max: 2 })
+        ));
+        assert!(matches!(
+            <MqttVarInt as Encoder<u32>>::encode(
+                &(MqttVarInt::MAX + 1),
+                &mut BytesMut::new()
+            ),
+            Err(Error::VarIntOverflow)
+        ));
+        assert!(matches!(
+            <Sleb128 as RawDecoder<usize>>::raw_decode(&[0x7F]),
+            Err(Error::IntConversion(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn usize_size_of_test() {
+        assert_eq!(<MqttVarInt as Encoder<usize>>::size_of(&usize::MAX), 4);
+        assert_eq!(<Sleb128 as Encoder<usize>>::size_of(&usize::MAX), 10);
+    }
+
+    macro_rules! test_varint_prefix {
+        ($name:ident; $c:ty) => {
+            #[test]
+            fn $name() {
+                let len: usize = (0..=20000).fake();
+                let buffer = Bytes::from_iter((0..len).map(|i| i as u8));
+                let mut bytes = BytesMut::new();
+
+                LengthPrefixed::<$c>::encode(&buffer, &mut bytes).unwrap();
+
+                assert_eq!(bytes.len(), LengthPrefixed::<$c>::size_of(&buffer));
+                assert_eq!(
+                    bytes.len(),
+                    <$c as Encoder<usize>>::size_of(&len) + len
+                );
+
+                let decoded: Bytes =
+                    LengthPrefixed::<$c>::decode(&mut bytes).unwrap();
+
+                assert_eq!(decoded, buffer);
+                assert!(bytes.is_empty());
+            }
+        };
+    }
+
+    test_varint_prefix!(uleb128_prefix_test; Uleb128);
+    test_varint_prefix!(sleb128_prefix_test; Sleb128);
+    test_varint_prefix!(zigzag_prefix_test; ZigZag);
+    test_varint_prefix!(quic_prefix_test; QuicVarInt);
+    test_varint_prefix!(mqtt_prefix_test; MqttVarInt);
+}
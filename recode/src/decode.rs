@@ -1,11 +1,23 @@
-use bytes::BytesMut;
+use bytes::Buf;
 
-/// A trait for types that can decode values of type [`Decoder::Output`] from
-/// a bytes buffer of type [`Bytes`](bytes::Bytes).
+/// A trait for types that can decode values of type `Item` from a buffer.
+///
+/// The buffer can be any [`Buf`], including non-contiguous ones (e.g. a
+/// [`Chain`](bytes::buf::Chain) of two buffers, or a `VecDeque<u8>`). Byte
+/// payloads are split off with [`Buf::copy_to_bytes`], which does not copy
+/// when decoding from [`Bytes`](bytes::Bytes) or
+/// [`BytesMut`](bytes::BytesMut).
 pub trait Decoder<Item = Self> {
     /// The type of error that can occur if decoding fails.
     type Error;
 
+    /// The minimum number of bytes a value is decoded from.
+    const MIN_SIZE: usize = 0;
+
+    /// The maximum number of bytes a value is decoded from, or `None` if it is
+    /// unbounded.
+    const MAX_SIZE: Option<usize> = None;
+
     /// Decodes a value from the given buffer.
     ///
     /// # Arguments
@@ -13,14 +25,53 @@ pub trait Decoder<Item = Self> {
     ///
     /// # Returns
     /// The decoded value.
-    fn decode(buf: &mut BytesMut) -> Result<Item, Self::Error>;
+    fn decode<B: Buf>(buf: &mut B) -> Result<Item, Self::Error>;
 
     /// Returns `true` if the given buffer can be decoded.
     ///
     /// This is used to determine if the buffer has enough bytes to decode a
-    /// value.
-    fn has_enough_bytes(_buf: &BytesMut) -> bool {
-        true
+    /// value. By default, this checks that the buffer holds at least
+    /// [`MIN_SIZE`](Self::MIN_SIZE) bytes.
+    fn has_enough_bytes<B: Buf>(buf: &B) -> bool {
+        buf.remaining() >= Self::MIN_SIZE
+    }
+}
+
+/// A trait for types that can decode values of type `Item` from a buffer,
+/// given a context of type `Ctx`.
+///
+/// The context carries runtime configuration (e.g. a protocol version, or
+/// negotiated limits) through nested values. Every [`Decoder`] implements
+/// this for any context, by ignoring it.
+pub trait DecodeWith<Ctx: ?Sized, Item = Self> {
+    /// The type of error that can occur if decoding fails.
+    type Error;
+
+    /// Decodes a value from the given buffer, using the given context.
+    ///
+    /// # Arguments
+    /// * `buf` - The buffer to decode the value from.
+    /// * `ctx` - The decoding context.
+    ///
+    /// # Returns
+    /// The decoded value.
+    fn decode_with<B: Buf>(buf: &mut B, ctx: &Ctx)
+        -> Result<Item, Self::Error>;
+}
+
+impl<T, Ctx, Item> DecodeWith<Ctx, Item> for T
+where
+    T: Decoder<Item>,
+    Ctx: ?Sized,
+{
+    type Error = T::Error;
+
+    #[inline]
+    fn decode_with<B: Buf>(
+        buf: &mut B,
+        _ctx: &Ctx,
+    ) -> Result<Item, Self::Error> {
+        T::decode(buf)
     }
 }
 
@@ -40,6 +91,39 @@ pub trait RawDecoder<Item = Self> {
         Item: 'a;
 }
 
+/// A trait for types that can decode values of type `Item` which borrow from
+/// the slice they are decoded from (e.g. `&'a [u8]` or `&'a str` fields).
+///
+/// Every [`RawDecoder`] implements this for any lifetime its values outlive,
+/// so borrowed values can be decoded along with owned ones. Deriving
+/// `RawDecoder` on a type with a lifetime implements this trait instead.
+pub trait BorrowDecoder<'a, Item = Self> {
+    /// The type of error that can occur if decoding fails.
+    type Error;
+
+    /// Decodes a value, borrowing from the given slice.
+    ///
+    /// # Arguments
+    /// * `buf` - The slice to decode the value from.
+    ///
+    /// # Returns
+    /// The decoded value and the number of bytes read.
+    fn borrow_decode(buf: &'a [u8]) -> Result<(Item, usize), Self::Error>;
+}
+
+impl<'a, T, Item> BorrowDecoder<'a, Item> for T
+where
+    T: RawDecoder<Item>,
+    Item: 'a,
+{
+    type Error = T::Error;
+
+    #[inline]
+    fn borrow_decode(buf: &'a [u8]) -> Result<(Item, usize), Self::Error> {
+        T::raw_decode(buf)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::ops::Deref;
@@ -103,4 +187,520 @@ mod tests {
         );
         assert_eq!(bytes.as_ref(), b"to be left");
     }
+
+    #[test]
+    fn tagged_enum_test() {
+        #[derive(Debug, PartialEq, Eq, Decoder)]
+        #[recode(tag = "u8", decoder(error = "crate::Error"))]
+        enum TestType {
+            #[recode(id = 0x01)]
+            Ping,
+            #[recode(id = 0x02)]
+            Data {
+                id: u16,
+                #[recode(decoder(with = "LengthPrefixed::<u8>"))]
+                payload: Bytes,
+            },
+        }
+
+        let mut bytes = BytesMut::from_iter([
+            0x02, 0x12, 0x34, 0x03, b'a', b'b', b'c', 0x01, 0x03,
+        ]);
+
+        assert_eq!(
+            TestType::decode(&mut bytes).unwrap(),
+            TestType::Data {
+                id: 0x1234,
+                payload: Bytes::from_static(b"abc"),
+            }
+        );
+        assert_eq!(TestType::decode(&mut bytes).unwrap(), TestType::Ping);
+
+        let err = TestType::decode(&mut bytes).unwrap_err();
+
+        assert_eq!(err.kind(), crate::ErrorKind::UnknownTag);
+        assert!(matches!(err.root(), crate::Error::UnknownTag(0x03)));
+    }
+
+    #[test]
+    fn tuple_struct_test() {
+        #[derive(Decoder)]
+        #[recode(decoder(error = "crate::Error"))]
+        struct Header(
+            u8,
+            #[recode(decoder(skip))] u32,
+            u16,
+            #[recode(decoder(with = "LengthPrefixed::<u8>"))] Bytes,
+        );
+
+        #[derive(Decoder)]
+        #[recode(transparent)]
+        struct PacketId(u16);
+
+        let mut bytes = BytesMut::from_iter([
+            0x01, 0x02, 0x03, 0x02, b'h', b'i', 0xAB, 0xCD,
+        ]);
+
+        let header = Header::decode(&mut bytes).unwrap();
+
+        assert_eq!(header.0, 0x01);
+        assert_eq!(header.1, 0);
+        assert_eq!(header.2, 0x0203);
+        assert_eq!(header.3.as_ref(), b"hi");
+
+        assert!(PacketId::has_enough_bytes(&bytes));
+        assert_eq!(PacketId::decode(&mut bytes).unwrap().0, 0xABCD);
+        assert!(!PacketId::has_enough_bytes(&bytes));
+        assert!(matches!(
+            PacketId::decode(&mut bytes),
+            Err(crate::Error::BytesNeeded { needed: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn little_endian_test() {
+        #[derive(Decoder)]
+        #[recode(endian = "little", decoder(error = "crate::Error"))]
+        struct TestType {
+            a: u16,
+            #[recode(decoder(with = "u16"))]
+            b: u16,
+            c: u32,
+        }
+
+        let mut bytes = BytesMut::from_iter([
+            0x01, 0x02, 0x01, 0x02, 0x01, 0x02, 0x03, 0x04,
+        ]);
+        let test = TestType::decode(&mut bytes).unwrap();
+
+        assert_eq!(test.a, 0x0201);
+        assert_eq!(test.b, 0x0102);
+        assert_eq!(test.c, 0x04030201);
+    }
+
+    #[test]
+    fn error_context_test() {
+        #[allow(dead_code)]
+        #[derive(Debug, Decoder)]
+        #[recode(decoder(error = "crate::Error"))]
+        struct Flags {
+            retain: u8,
+            qos: u8,
+        }
+
+        #[allow(dead_code)]
+        #[derive(Debug, Decoder)]
+        struct Header {
+            id: u16,
+            flags: Flags,
+        }
+
+        let mut bytes = BytesMut::from_iter([0x00, 0x01, 0x01]);
+        let err = Header::decode(&mut bytes).unwrap_err();
+        let err = err.downcast_ref::<crate::Error>().unwrap();
+        let context = err.context().unwrap();
+
+        assert_eq!(err.kind(), crate::ErrorKind::Incomplete);
+        assert_eq!(context.ty, "Header");
+        assert_eq!(context.path, ["flags", "qos"]);
+        assert_eq!(context.offset, 3);
+        assert_eq!(
+            err.to_string(),
+            "Header.flags.qos at byte offset 3: 1 more bytes needed to read 1 \
+             bytes (0 bytes available)"
+        );
+    }
+
+    #[test]
+    fn non_contiguous_test() {
+        use std::collections::VecDeque;
+
+        use bytes::Buf;
+
+        use crate::codec::{Crlf, Delimited, Uleb128};
+
+        #[derive(Debug, PartialEq, Eq, Decoder)]
+        #[recode(decoder(error = "crate::Error"))]
+        struct TestType {
+            id: u32,
+            #[recode(decoder(with = "LengthPrefixed::<u8>"))]
+            name: Bytes,
+            #[recode(decoder(with = "Uleb128"))]
+            size: u64,
+            #[recode(decoder(with = "Delimited::<Crlf>"))]
+            line: String,
+        }
+
+        const BUF: &[u8] = b"\x01\x02\x03\x04\x03abc\xE5\x8E\x26hi\r\n";
+
+        let expected = TestType {
+            id: 0x01020304,
+            name: Bytes::from_static(b"abc"),
+            size: 624485,
+            line: "hi".to_owned(),
+        };
+
+        for i in 0..BUF.len() {
+            let mut chain = (&BUF[..i]).chain(&BUF[i..]);
+            let mut ring = VecDeque::from(BUF[i..].to_vec());
+
+            ring.extend(&BUF[..i]);
+            ring.rotate_right(i);
+
+            assert!(TestType::has_enough_bytes(&chain));
+            assert_eq!(TestType::decode(&mut chain).unwrap(), expected);
+            assert!(!chain.has_remaining());
+            assert_eq!(TestType::decode(&mut ring).unwrap(), expected);
+            assert!(ring.is_empty());
+        }
+
+        let mut slice = BUF;
+        let mut bytes = Bytes::from_static(BUF);
+
+        assert_eq!(TestType::decode(&mut slice).unwrap(), expected);
+        assert_eq!(TestType::decode(&mut bytes).unwrap(), expected);
+    }
+
+    #[test]
+    fn context_test() {
+        use crate::DecodeWith;
+
+        struct Version(u8);
+
+        #[derive(Debug, PartialEq, Eq, Decoder)]
+        #[recode(ctx = "Version", decoder(error = "crate::Error"))]
+        struct Options {
+            #[recode(decoder(skip_if = "ctx.0 < 3"))]
+            flags: u8,
+        }
+
+        #[derive(Debug, PartialEq, Eq, Decoder)]
+        #[recode(ctx = "Version", decoder(error = "crate::Error"))]
+        struct Connect {
+            id: u16,
+            options: Options,
+            #[recode(decoder(skip_if = "ctx.0 < 5"))]
+            expiry: u32,
+        }
+
+        let mut bytes = BytesMut::from_iter([0x00, 0x01, 0x02]);
+        let connect = Connect::decode_with(&mut bytes, &Version(3)).unwrap();
+
+        assert_eq!(connect.id, 0x0001);
+        assert_eq!(connect.options, Options { flags: 0x02 });
+        assert_eq!(connect.expiry, 0);
+        assert!(bytes.is_empty());
+
+        let mut bytes =
+            BytesMut::from_iter([0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x3C]);
+        let connect = Connect::decode_with(&mut bytes, &Version(5)).unwrap();
+
+        assert_eq!(connect.options, Options { flags: 0x00 });
+        assert_eq!(connect.expiry, 0x3C);
+        assert!(bytes.is_empty());
+
+        let mut bytes = BytesMut::from_iter([0x00, 0x01]);
+        let connect = Connect::decode_with(&mut bytes, &Version(1)).unwrap();
+
+        assert_eq!(connect.options, Options { flags: 0x00 });
+        // a field named `ctx` does not shadow the context of nested values
+        #[derive(Debug, PartialEq, Eq, Decoder)]
+        #[recode(ctx = "Version", decoder(error = "crate::Error"))]
+        struct Shadowed {
+            ctx: u8,
+            options: Options,
+        }
+
+        let mut bytes = BytesMut::from_iter([0x01, 0x02]);
+
+        assert_eq!(
+            Shadowed::decode_with(&mut bytes, &Version(3)).unwrap(),
+            Shadowed {
+                ctx: 0x01,
+                options: Options { flags: 0x02 },
+            }
+        );
+    }
+
+    #[test]
+    fn length_field_test() {
+        use crate::codec::Unprefixed;
+
+        #[derive(Debug, PartialEq, Eq, Decoder)]
+        #[recode(decoder(error = "crate::Error"))]
+        struct Record {
+            id: u8,
+            #[recode(decoder(with = "LengthPrefixed::<u8>"))]
+            value: Bytes,
+        }
+
+        #[derive(Debug, PartialEq, Eq, Decoder)]
+        #[recode(decoder(error = "crate::Error"))]
+        struct Message {
+            len: u16,
+            count: u8,
+            flags: u8,
+            #[recode(decoder(len = "len as usize", with = "Unprefixed"))]
+            payload: Bytes,
+            #[recode(decoder(count = "count as usize"))]
+            records: Vec<Record>,
+            #[recode(decoder(count = "2", with = "crate::codec::Le<u16>"))]
+            trailer: Vec<u16>,
+        }
+
+        let mut bytes = BytesMut::from(
+            &b"\x00\x03\x02\xFFabc\x01\x00\x02\x01z\x01\x00\x02\x00rest"[..],
+        );
+        let message = Message::decode(&mut bytes).unwrap();
+
+        assert_eq!(message.len, 3);
+        assert_eq!(message.flags, 0xFF);
+        assert_eq!(&message.payload[..], b"abc");
+        assert_eq!(
+            message.records,
+            [
+                Record {
+                    id: 0x01,
+                    value: Bytes::new(),
+                },
+                Record {
+                    id: 0x02,
+                    value: Bytes::from_static(b"z"),
+                },
+            ]
+        );
+        assert_eq!(message.trailer, [0x0001, 0x0002]);
+        assert_eq!(&bytes[..], b"rest");
+
+        #[allow(dead_code)]
+        #[derive(Debug, Decoder)]
+        #[recode(decoder(error = "crate::Error"))]
+        struct Framed {
+            len: u8,
+            #[recode(decoder(len = "len as usize"))]
+            value: u16,
+        }
+
+        let mut short = BytesMut::from_iter([0x03, 0x00, 0x01]);
+        let mut trailing = BytesMut::from_iter([0x03, 0x00, 0x01, 0x02]);
+        let mut truncated = BytesMut::from_iter([0x01, 0x00, 0x01]);
+
+        assert!(matches!(
+            Framed::decode(&mut short).unwrap_err().root(),
+            crate::Error::BytesNeeded {
+                needed: 1,
+                full_len: 3,
+                available: 2
+            }
+        ));
+        assert!(matches!(
+            Framed::decode(&mut trailing).unwrap_err().root(),
+            crate::Error::TrailingBytes(1)
+        ));
+
+        let err = Framed::decode(&mut truncated).unwrap_err();
+
+        assert!(matches!(
+            err.root(),
+            crate::Error::TruncatedFrame { len: 1 }
+        ));
+        assert_eq!(err.context().unwrap().path, ["value"]);
+    }
+
+    #[test]
+    fn magic_test() {
+        #[derive(Debug, PartialEq, Eq, Decoder)]
+        #[recode(magic = b"\x89PNG", decoder(error = "crate::Error"))]
+        struct Header {
+            #[recode(before(constant(ty = "u8", value = 0x01), reserved = 3))]
+            len: u16,
+            #[recode(after(magic = b"\r\n"))]
+            kind: u8,
+        }
+
+        let mut bytes =
+            BytesMut::from(&b"\x89PNG\x01\x00\x00\x00\x00\x10\x07\r\nrest"[..]);
+
+        assert_eq!(
+            Header::decode(&mut bytes).unwrap(),
+            Header {
+                len: 0x10,
+                kind: 0x07,
+            }
+        );
+        assert_eq!(&bytes[..], b"rest");
+
+        let mut magic =
+            BytesMut::from(&b"\x89PNX\x01\x00\x00\x00\x00\x10\x07\r\n"[..]);
+        let mut version =
+            BytesMut::from(&b"\x89PNG\x02\x00\x00\x00\x00\x10\x07\r\n"[..]);
+        let mut reserved =
+            BytesMut::from(&b"\x89PNG\x01\x00\x00\x01\x00\x10\x07\r\n"[..]);
+        let mut trailer =
+            BytesMut::from(&b"\x89PNG\x01\x00\x00\x00\x00\x10\x07\n\r"[..]);
+
+        let err = Header::decode(&mut magic).unwrap_err();
+
+        assert!(matches!(err.root(), crate::Error::InvalidMagic));
+        assert_eq!(err.context().unwrap().offset, 0);
+
+        let err = Header::decode(&mut version).unwrap_err();
+
+        assert_eq!(
+            err.root().to_string(),
+            "invalid constant: expected 1, found 2"
+        );
+        assert_eq!(err.context().unwrap().path, ["len"]);
+        assert_eq!(err.context().unwrap().offset, 4);
+
+        let err = Header::decode(&mut reserved).unwrap_err();
+
+        assert!(matches!(
+            err.root(),
+            crate::Error::NonZeroReserved { offset: 2 }
+        ));
+        assert_eq!(err.context().unwrap().offset, 5);
+
+        let err = Header::decode(&mut trailer).unwrap_err();
+
+        assert!(matches!(err.root(), crate::Error::InvalidMagic));
+        assert_eq!(err.context().unwrap().path, ["kind"]);
+        assert_eq!(err.context().unwrap().offset, 11);
+    }
+
+    #[test]
+    fn size_bounds_test() {
+        #[allow(dead_code)]
+        #[derive(Decoder)]
+        #[recode(magic = b"RC", decoder(error = "crate::Error"))]
+        struct Fixed {
+            #[recode(after(reserved = 1))]
+            kind: u8,
+            id: [u8; 3],
+            #[recode(before(constant(ty = "u16", value = 1)))]
+            values: [u16; 2],
+        }
+
+        #[allow(dead_code)]
+        #[derive(Decoder)]
+        struct Variable {
+            kind: u8,
+            #[recode(decoder(skip_if = "kind == 0"))]
+            value: u32,
+            #[recode(decoder(with = "LengthPrefixed::<u8>"))]
+            name: Bytes,
+        }
+
+        #[allow(dead_code)]
+        #[derive(Decoder)]
+        #[recode(tag = "u8")]
+        enum Message {
+            #[recode(id = 0)]
+            Ping,
+            #[recode(id = 1)]
+            Data(u16, u32),
+        }
+
+        assert_eq!(<Fixed as Decoder>::MIN_SIZE, 13);
+        assert_eq!(<Fixed as Decoder>::MAX_SIZE, Some(13));
+        assert!(Fixed::has_enough_bytes(&Bytes::from_static(&[0; 13])));
+        assert!(!Fixed::has_enough_bytes(&Bytes::from_static(&[0; 12])));
+
+        assert_eq!(<Variable as Decoder>::MIN_SIZE, 2);
+        assert_eq!(<Variable as Decoder>::MAX_SIZE, None);
+        assert!(Variable::has_enough_bytes(&Bytes::from_static(&[0; 2])));
+        assert!(!Variable::has_enough_bytes(&Bytes::from_static(&[0])));
+
+        assert_eq!(<Message as Decoder>::MIN_SIZE, 1);
+        assert_eq!(<Message as Decoder>::MAX_SIZE, Some(7));
+    }
+
+    #[test]
+    fn raw_decoder_test() {
+        use crate::{codec::Le, BorrowDecoder, RawDecoder};
+
+        #[derive(Debug, PartialEq, Eq, RawDecoder)]
+        #[recode(magic = b"PK", decoder(error = "crate::Error"))]
+        struct Record<'a> {
+            #[recode(decoder(with = "Le<u16>"))]
+            id: u16,
+            #[recode(
+                decoder(with = "LengthPrefixed<u8>"),
+                after(reserved = 1)
+            )]
+            name: &'a [u8],
+            #[recode(decoder(with = "LengthPrefixed<u16>"))]
+            text: &'a str,
+            flags: u8,
+            #[recode(skip_if = "flags == 0")]
+            extra: u8,
+        }
+
+        #[derive(Debug, PartialEq, Eq, RawDecoder)]
+        #[recode(tag = "u8", decoder(error = "crate::Error"))]
+        enum Packet<'a> {
+            #[recode(id = 1)]
+            Ping(u32),
+            #[recode(id = 2)]
+            Data {
+                #[recode(decoder(with = "crate::codec::Unprefixed"))]
+                payload: &'a [u8],
+            },
+        }
+
+        #[derive(Debug, PartialEq, Eq, RawDecoder)]
+        struct Point {
+            x: u16,
+            y: u16,
+        }
+
+        let input = b"PK\x02\x01\x03abc\x00\x00\x02hi\x00rest";
+        let (record, rx) = Record::borrow_decode(input).unwrap();
+
+        assert_eq!(record.name, b"abc");
+        assert_eq!(record.name.as_ptr(), input[5..].as_ptr());
+        assert_eq!(record.text, "hi");
+        assert_eq!(record.id, 0x0102);
+        assert_eq!(record.extra, 0);
+        assert_eq!(rx, input.len() - 4);
+
+        let err = Record::borrow_decode(b"PK\x02\x01\x05abc").unwrap_err();
+
+        assert!(matches!(err.root(), crate::Error::BytesNeeded { .. }));
+        assert_eq!(err.context().unwrap().path, ["name"]);
+        assert_eq!(err.context().unwrap().offset, 4);
+
+        let err = Record::borrow_decode(b"PK\x02\x01\x03abc\x07\x00\x02hi\x00")
+            .unwrap_err();
+
+        assert!(matches!(
+            err.root(),
+            crate::Error::NonZeroReserved { offset: 0 }
+        ));
+        assert_eq!(err.context().unwrap().path, ["name"]);
+        assert_eq!(err.context().unwrap().offset, 8);
+
+        assert_eq!(
+            Packet::borrow_decode(b"\x02\x01\x02").unwrap(),
+            (Packet::Data { payload: &[1, 2] }, 3)
+        );
+        assert_eq!(
+            Packet::borrow_decode(b"\x01\x00\x00\x00\x07").unwrap(),
+            (Packet::Ping(7), 5)
+        );
+        assert!(matches!(
+            Packet::borrow_decode(b"\x03").unwrap_err().root(),
+            crate::Error::UnknownTag(3)
+        ));
+
+        let (point, rx) = Point::raw_decode(&[0x00, 0x01, 0x00, 0x02]).unwrap();
+
+        assert_eq!((point, rx), (Point { x: 1, y: 2 }, 4));
+        assert_eq!(
+            <[Point; 2]>::raw_decode(&[0, 1, 0, 2, 0, 3, 0, 4])
+                .unwrap()
+                .0,
+            [Point { x: 1, y: 2 }, Point { x: 3, y: 4 }]
+        );
+    }
 }
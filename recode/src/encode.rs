@@ -1,17 +1,33 @@
-use bytes::BytesMut;
+use bytes::BufMut;
 
 /// A trait to be implemented by types that encode [`Item`] values into a
-/// buffer of type [`BytesMut`](bytes::BytesMut).
-pub trait Encoder<Item = Self> {
+/// buffer.
+///
+/// The buffer can be any [`BufMut`], including bounded ones (e.g. `&mut [u8]`
+/// or [`Limit`](bytes::buf::Limit)). When encoding through
+/// [`EncoderExt::encode_to`](crate::util::EncoderExt::encode_to), derived
+/// encoders check that the buffer has room for the whole value before writing
+/// to it, and fail with
+/// [`Error::BufferTooSmall`](crate::Error::BufferTooSmall) otherwise.
+pub trait Encoder<Item: ?Sized = Self> {
     /// The type of error that can occur if encoding fails.
     type Error;
 
+    /// The minimum number of bytes a value is encoded into.
+    const MIN_SIZE: usize = 0;
+
+    /// The maximum number of bytes a value is encoded into, or `None` if it is
+    /// unbounded.
+    ///
+    /// This can be used to size buffers at compile time.
+    const MAX_SIZE: Option<usize> = None;
+
     /// Encodes the given input into the output buffer.
     ///
     /// # Arguments
     /// * `item` - The input to encode.
     /// * `buf` - The output buffer to write the encoded input to.
-    fn encode(item: &Item, buf: &mut BytesMut) -> Result<(), Self::Error>;
+    fn encode<B: BufMut>(item: &Item, buf: &mut B) -> Result<(), Self::Error>;
 
     /// Returns the number of bytes required to encode the given input.
     ///
@@ -25,6 +41,71 @@ pub trait Encoder<Item = Self> {
     /// # Returns
     /// The number of bytes required to encode the given input.
     fn size_of(item: &Item) -> usize;
+
+    /// Checks that the output buffer has room for the given input.
+    ///
+    /// This is called once by
+    /// [`EncoderExt::encode_to`](crate::util::EncoderExt::encode_to) before
+    /// encoding, rather than by every nested encoder. By default, nothing is
+    /// checked.
+    #[inline]
+    fn check_capacity<B: BufMut>(
+        _item: &Item,
+        _buf: &B,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// A trait to be implemented by types that encode [`Item`] values into a
+/// buffer, given a context of type `Ctx`.
+///
+/// The context carries runtime configuration (e.g. a protocol version, or
+/// negotiated limits) through nested values. Every [`Encoder`] implements
+/// this for any context, by ignoring it.
+pub trait EncodeWith<Ctx: ?Sized, Item: ?Sized = Self> {
+    /// The type of error that can occur if encoding fails.
+    type Error;
+
+    /// Encodes the given input into the output buffer, using the given
+    /// context.
+    ///
+    /// # Arguments
+    /// * `item` - The input to encode.
+    /// * `buf` - The output buffer to write the encoded input to.
+    /// * `ctx` - The encoding context.
+    fn encode_with<B: BufMut>(
+        item: &Item,
+        buf: &mut B,
+        ctx: &Ctx,
+    ) -> Result<(), Self::Error>;
+
+    /// Returns the number of bytes required to encode the given input, using
+    /// the given context.
+    fn size_of_with(item: &Item, ctx: &Ctx) -> usize;
+}
+
+impl<T, Ctx, Item> EncodeWith<Ctx, Item> for T
+where
+    T: Encoder<Item>,
+    Ctx: ?Sized,
+    Item: ?Sized,
+{
+    type Error = T::Error;
+
+    #[inline]
+    fn encode_with<B: BufMut>(
+        item: &Item,
+        buf: &mut B,
+        _ctx: &Ctx,
+    ) -> Result<(), Self::Error> {
+        T::encode(item, buf)
+    }
+
+    #[inline]
+    fn size_of_with(item: &Item, _ctx: &Ctx) -> usize {
+        T::size_of(item)
+    }
 }
 
 #[cfg(test)]
@@ -95,4 +176,583 @@ mod tests {
 
         assert_eq!(buf, BUF.as_ref());
     }
+
+    #[test]
+    fn tagged_enum_test() {
+        #[derive(Encoder)]
+        #[recode(tag = "u8", encoder(error = "crate::Error"))]
+        enum TestType {
+            #[recode(id = 0x01)]
+            Ping,
+            #[recode(id = 0x02)]
+            Data {
+                id: u16,
+                #[recode(encoder(with = "LengthPrefixed::<u8>"))]
+                payload: Bytes,
+            },
+        }
+
+        let ping = TestType::Ping;
+        let data = TestType::Data {
+            id: 0x1234,
+            payload: Bytes::from_static(b"abc"),
+        };
+
+        assert_eq!(ping.size(), 1);
+        assert_eq!(data.size(), 1 + 2 + (1 + 3));
+
+        let mut buf = bytes::BytesMut::new();
+
+        data.encode_to(&mut buf).unwrap();
+        ping.encode_to(&mut buf).unwrap();
+
+        assert_eq!(
+            buf,
+            [0x02, 0x12, 0x34, 0x03, b'a', b'b', b'c', 0x01].as_ref()
+        );
+    }
+
+    #[test]
+    fn tuple_struct_test() {
+        #[derive(Encoder)]
+        #[recode(encoder(error = "crate::Error"))]
+        struct Header(
+            u8,
+            #[recode(encoder(skip))] u32,
+            u16,
+            #[recode(encoder(with = "LengthPrefixed::<u8>"))] Bytes,
+        );
+
+        #[derive(Encoder)]
+        #[recode(transparent)]
+        struct PacketId(u16);
+
+        let header =
+            Header(0x01, 0xFFFFFFFF, 0x0203, Bytes::from_static(b"hi"));
+        let id = PacketId(0xABCD);
+
+        assert_eq!(header.size(), 1 + 2 + (1 + 2));
+        assert_eq!(id.size(), 2);
+
+        let mut buf = bytes::BytesMut::new();
+
+        header.encode_to(&mut buf).unwrap();
+        id.encode_to(&mut buf).unwrap();
+
+        assert_eq!(
+            buf,
+            [0x01, 0x02, 0x03, 0x02, b'h', b'i', 0xAB, 0xCD].as_ref()
+        );
+    }
+
+    #[test]
+    fn little_endian_test() {
+        #[derive(Encoder)]
+        #[recode(endian = "little", encoder(error = "crate::Error"))]
+        struct TestType {
+            a: u16,
+            #[recode(encoder(with = "u16"))]
+            b: u16,
+            c: u32,
+        }
+
+        let test = TestType {
+            a: 0x0201,
+            b: 0x0102,
+            c: 0x04030201,
+        };
+        let mut buf = bytes::BytesMut::new();
+
+        test.encode_to(&mut buf).unwrap();
+
+        assert_eq!(
+            buf,
+            [0x01, 0x02, 0x01, 0x02, 0x01, 0x02, 0x03, 0x04].as_ref()
+        );
+    }
+
+    #[test]
+    fn buf_mut_test() {
+        use bytes::BufMut;
+
+        #[derive(Encoder)]
+        #[recode(encoder(error = "crate::Error"))]
+        struct TestType {
+            id: u32,
+            #[recode(encoder(with = "LengthPrefixed::<u8>"))]
+            name: Bytes,
+        }
+
+        let test = TestType {
+            id: 0x01020304,
+            name: Bytes::from_static(b"abc"),
+        };
+        let expected = b"\x01\x02\x03\x04\x03abc";
+
+        let mut vec = Vec::new();
+        let mut array = [0u8; 8];
+        let mut short = [0u8; 7];
+
+        test.encode_to(&mut vec).unwrap();
+        test.encode_to(&mut &mut array[..]).unwrap();
+
+        assert_eq!(vec, expected);
+        assert_eq!(&array, expected);
+        assert!(matches!(
+            test.encode_to(&mut &mut short[..]),
+            Err(crate::Error::BufferTooSmall {
+                needed: 8,
+                available: 7
+            })
+        ));
+        assert_eq!(short, [0; 7]);
+        assert!(matches!(
+            test.encode_to(&mut Vec::new().limit(4)),
+            Err(crate::Error::BufferTooSmall {
+                needed: 8,
+                available: 4
+            })
+        ));
+
+        #[derive(Encoder)]
+        #[recode(encoder(error = "crate::Error"))]
+        struct Outer {
+            kind: u8,
+            inner: TestType,
+        }
+
+        let outer = Outer {
+            kind: 0x05,
+            inner: test,
+        };
+        let mut short = [0u8; 8];
+
+        assert!(matches!(
+            outer.encode_to(&mut &mut short[..]),
+            Err(crate::Error::BufferTooSmall {
+                needed: 9,
+                available: 8
+            })
+        ));
+        assert_eq!(short, [0; 8]);
+    }
+
+    #[test]
+    fn context_test() {
+        use crate::EncodeWith;
+
+        struct Version(u8);
+
+        #[derive(Encoder)]
+        #[recode(ctx = "Version", encoder(error = "crate::Error"))]
+        struct Options {
+            #[recode(encoder(skip_if = "ctx.0 < 3"))]
+            flags: u8,
+        }
+
+        #[derive(Encoder)]
+        #[recode(ctx = "Version", encoder(error = "crate::Error"))]
+        struct Connect {
+            id: u16,
+            options: Options,
+            #[recode(encoder(skip_if = "ctx.0 < 5"))]
+            expiry: u32,
+        }
+
+        let connect = Connect {
+            id: 0x0001,
+            options: Options { flags: 0x02 },
+            expiry: 0x3C,
+        };
+
+        for (version, expected) in [
+            (1, &[0x00, 0x01][..]),
+            (3, &[0x00, 0x01, 0x02]),
+            (5, &[0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x3C]),
+        ] {
+            let mut bytes = Vec::new();
+
+            Connect::encode_with(&connect, &mut bytes, &Version(version))
+                .unwrap();
+
+            assert_eq!(&bytes[..], expected);
+            assert_eq!(
+                Connect::size_of_with(&connect, &Version(version)),
+                expected.len()
+            );
+        }
+
+        // a field named `ctx` does not shadow the context of nested values
+        #[derive(Encoder)]
+        #[recode(ctx = "Version", encoder(error = "crate::Error"))]
+        struct Shadowed {
+            ctx: u8,
+            options: Options,
+        }
+
+        let shadowed = Shadowed {
+            ctx: 0x01,
+            options: Options { flags: 0x02 },
+        };
+        let mut bytes = Vec::new();
+
+        Shadowed::encode_with(&shadowed, &mut bytes, &Version(3)).unwrap();
+
+        assert_eq!(bytes, [0x01, 0x02]);
+        assert_eq!(Shadowed::size_of_with(&shadowed, &Version(1)), 1);
+    }
+
+    #[test]
+    fn value_test() {
+        use crate::codec::{Repeated, Unprefixed};
+
+        #[derive(Encoder)]
+        #[recode(encoder(error = "crate::Error"))]
+        struct Message {
+            #[recode(encoder(value = "payload.len() as u16"))]
+            len: u16,
+            #[recode(encoder(value = "items.len() as u8"))]
+            count: u8,
+            #[recode(encoder(with = "Unprefixed"))]
+            payload: Bytes,
+            #[recode(encoder(with = "Repeated<u8>"))]
+            items: Vec<u8>,
+        }
+
+        let message = Message {
+            len: 0,
+            count: 7,
+            payload: Bytes::from_static(b"abc"),
+            items: vec![0x01, 0x02],
+        };
+        let mut bytes = Vec::new();
+
+        message.encode_to(&mut bytes).unwrap();
+
+        assert_eq!(bytes, b"\x00\x03\x02abc\x01\x02");
+        assert_eq!(message.size(), bytes.len());
+    }
+
+    #[test]
+    fn magic_test() {
+        #[derive(Encoder)]
+        #[recode(magic = b"\x89PNG", encoder(error = "crate::Error"))]
+        struct Header {
+            #[recode(before(constant(ty = "u8", value = 0x01), reserved = 3))]
+            len: u16,
+            #[recode(after(magic = b"\r\n"))]
+            kind: u8,
+        }
+
+        let header = Header {
+            len: 0x10,
+            kind: 0x07,
+        };
+        let mut bytes = Vec::new();
+
+        header.encode_to(&mut bytes).unwrap();
+
+        assert_eq!(bytes, b"\x89PNG\x01\x00\x00\x00\x00\x10\x07\r\n");
+        assert_eq!(header.size(), bytes.len());
+    }
+
+    #[test]
+    fn size_bounds_test() {
+        #[allow(dead_code)]
+        #[derive(Encoder)]
+        struct Fixed {
+            kind: u8,
+            values: [u32; 2],
+            #[recode(encoder(skip_if = "*kind == 0"))]
+            extra: u16,
+        }
+
+        #[allow(dead_code)]
+        #[derive(Encoder)]
+        struct Variable {
+            kind: u8,
+            #[recode(encoder(with = "LengthPrefixed::<u8>"))]
+            name: Bytes,
+        }
+
+        let mut buf = [0u8; <Fixed as Encoder>::MAX_SIZE.unwrap()];
+        let fixed = Fixed {
+            kind: 1,
+            values: [2, 3],
+            extra: 4,
+        };
+
+        fixed.encode_to(&mut &mut buf[..]).unwrap();
+
+        assert_eq!(<Fixed as Encoder>::MIN_SIZE, 9);
+        assert_eq!(buf.len(), fixed.size());
+        assert_eq!(<Variable as Encoder>::MIN_SIZE, 2);
+        assert_eq!(<Variable as Encoder>::MAX_SIZE, None);
+    }
+
+    #[test]
+    fn recode_options_test() {
+        use crate::{Decoder, Recode};
+
+        #[derive(Debug, PartialEq, Eq, Recode)]
+        #[recode(error = "crate::Error")]
+        struct Message {
+            #[recode(map(
+                decode = "|v: u8| v - 1",
+                encode = "|v: &u8| v + 1"
+            ))]
+            level: u8,
+            #[recode(
+                skip_if = "true",
+                decoder(skip_if = "level == 0"),
+                encoder(skip_if = "*level == 0")
+            )]
+            value: u16,
+            #[recode(
+                with = "LengthPrefixed<u8>",
+                encoder(with = "LengthPrefixed<u16>"),
+                size = "2 + name.len()"
+            )]
+            name: Bytes,
+        }
+
+        let message = Message {
+            level: 1,
+            value: 0x0203,
+            name: Bytes::from_static(b"ab"),
+        };
+        let mut bytes = Vec::new();
+
+        message.encode_to(&mut bytes).unwrap();
+
+        assert_eq!(bytes, b"\x02\x02\x03\x00\x02ab");
+        assert_eq!(message.size(), bytes.len());
+        assert_eq!(
+            Message::decode(&mut &b"\x02\x02\x03\x02ab"[..]).unwrap(),
+            message
+        );
+        assert_eq!(
+            Message::decode(&mut &b"\x01\x02ab"[..]).unwrap(),
+            Message {
+                level: 0,
+                value: 0,
+                name: Bytes::from_static(b"ab"),
+            }
+        );
+    }
+
+    #[test]
+    fn encoder_map_test() {
+        #[derive(Encoder)]
+        #[recode(encoder(error = "crate::Error"))]
+        struct Message {
+            #[recode(encoder(map = "clamp"))]
+            level: u8,
+        }
+
+        fn clamp(level: &u8) -> &u8 {
+            if *level > 3 {
+                &3
+            } else {
+                level
+            }
+        }
+
+        let mut bytes = Vec::new();
+
+        Message { level: 2 }.encode_to(&mut bytes).unwrap();
+        Message { level: 7 }.encode_to(&mut bytes).unwrap();
+
+        assert_eq!(bytes, b"\x02\x03");
+        assert_eq!(Message { level: 7 }.size(), 1);
+    }
+
+    #[test]
+    fn conversion_test() {
+        use crate::{Decoder, Error, Recode};
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        enum Kind {
+            Ping,
+            Pong,
+        }
+
+        impl TryFrom<u8> for Kind {
+            type Error = ();
+
+            fn try_from(value: u8) -> Result<Self, ()> {
+                match value {
+                    | 1 => Ok(Self::Ping),
+                    | 2 => Ok(Self::Pong),
+                    | _ => Err(()),
+                }
+            }
+        }
+
+        impl From<Kind> for u8 {
+            fn from(kind: Kind) -> Self {
+                match kind {
+                    | Kind::Ping => 1,
+                    | Kind::Pong => 2,
+                }
+            }
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        struct Millis(u32);
+
+        impl From<u32> for Millis {
+            fn from(value: u32) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<Millis> for u32 {
+            fn from(value: Millis) -> Self {
+                value.0
+            }
+        }
+
+        #[derive(Debug, PartialEq, Eq, Recode)]
+        #[recode(endian = "little", error = "crate::Error")]
+        struct Message {
+            #[recode(try_from = "u8")]
+            kind: Kind,
+            #[recode(via = "u32")]
+            timeout: Millis,
+            #[recode(try_from = "u8")]
+            len: usize,
+        }
+
+        let message = Message {
+            kind: Kind::Pong,
+            timeout: Millis(0x0102),
+            len: 3,
+        };
+        let mut bytes = Vec::new();
+
+        message.encode_to(&mut bytes).unwrap();
+
+        assert_eq!(bytes, b"\x02\x02\x01\x00\x00\x03");
+        assert_eq!(message.size(), bytes.len());
+        assert_eq!(<Message as Decoder>::MIN_SIZE, 6);
+        assert_eq!(Message::decode(&mut &bytes[..]).unwrap(), message);
+
+        let err =
+            Message::decode(&mut &b"\x03\x02\x01\x00\x00\x03"[..]).unwrap_err();
+
+        assert!(matches!(
+            err.root(),
+            Error::InvalidConversion {
+                field: "kind",
+                ty: "u8"
+            }
+        ));
+        assert_eq!(err.context().unwrap().path, ["kind"]);
+
+        let message = Message {
+            len: 256,
+            ..message
+        };
+        let err = message.encode_to(&mut Vec::new()).unwrap_err();
+
+        assert!(matches!(err, Error::InvalidConversion { field: "len", .. }));
+    }
+
+    #[test]
+    fn c_like_enum_test() {
+        use crate::{Decoder, Error, Recode};
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Recode)]
+        #[recode(error = "crate::Error")]
+        #[repr(u8)]
+        enum Qos {
+            AtMostOnce,
+            AtLeastOnce,
+            ExactlyOnce = 4,
+            Reserved,
+        }
+
+        let mut bytes = Vec::new();
+
+        Qos::AtLeastOnce.encode_to(&mut bytes).unwrap();
+        Qos::Reserved.encode_to(&mut bytes).unwrap();
+
+        assert_eq!(bytes, b"\x01\x05");
+        assert_eq!(Qos::Reserved.size(), 1);
+        assert_eq!(<Qos as Decoder>::MIN_SIZE, 1);
+        assert_eq!(Qos::decode(&mut &b"\x04"[..]).unwrap(), Qos::ExactlyOnce);
+        assert!(matches!(
+            Qos::decode(&mut &b"\x02"[..]).unwrap_err().root(),
+            Error::UnknownTag(2)
+        ));
+    }
+
+    #[test]
+    fn negative_discriminant_test() {
+        use crate::{Decoder, Error, Recode};
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Recode)]
+        #[recode(endian = "little", error = "crate::Error")]
+        #[repr(i16)]
+        enum Step {
+            Back = -2,
+            Stay,
+            Forward,
+        }
+
+        let mut bytes = Vec::new();
+
+        Step::Back.encode_to(&mut bytes).unwrap();
+        Step::Stay.encode_to(&mut bytes).unwrap();
+
+        assert_eq!(bytes, b"\xFE\xFF\xFF\xFF");
+        assert_eq!(Step::Forward as i16, 0);
+
+        let mut buf = &bytes[..];
+
+        assert_eq!(Step::decode(&mut buf).unwrap(), Step::Back);
+        assert_eq!(Step::decode(&mut buf).unwrap(), Step::Stay);
+        assert_eq!(Step::decode(&mut &b"\x00\x00"[..]).unwrap(), Step::Forward);
+        assert!(matches!(
+            Step::decode(&mut &b"\x01\x00"[..]).unwrap_err().root(),
+            Error::UnknownTag(1)
+        ));
+        assert!(matches!(
+            Step::decode(&mut &b"\xFD\xFF"[..]).unwrap_err().root(),
+            Error::IntConversion(_)
+        ));
+    }
+
+    #[cfg(feature = "ux")]
+    #[test]
+    fn c_like_enum_other_test() {
+        use crate::{codec::u24, Decoder, RawDecoder, Recode};
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Recode, RawDecoder)]
+        #[recode(endian = "little", repr = "u24", error = "crate::Error")]
+        enum Code {
+            #[recode(id = 0x0102)]
+            Ok,
+            #[recode(other)]
+            Unknown(u32),
+        }
+
+        let mut bytes = Vec::new();
+
+        Code::Ok.encode_to(&mut bytes).unwrap();
+        Code::Unknown(0x030201).encode_to(&mut bytes).unwrap();
+
+        assert_eq!(bytes, b"\x02\x01\x00\x01\x02\x03");
+        assert_eq!(<Code as Decoder>::MAX_SIZE, Some(3));
+
+        let mut buf = &bytes[..];
+
+        assert_eq!(Code::decode(&mut buf).unwrap(), Code::Ok);
+        assert_eq!(Code::decode(&mut buf).unwrap(), Code::Unknown(0x030201));
+        assert_eq!(
+            Code::raw_decode(&bytes[3..]).unwrap(),
+            (Code::Unknown(0x030201), 3)
+        );
+    }
 }
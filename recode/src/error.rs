@@ -1,7 +1,51 @@
+use std::{any::Any, fmt};
+
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The category of an [`Error`], used to tell incomplete input apart from
+/// corrupt input without matching on every variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// More bytes are needed to decode the value.
+    Incomplete,
+    /// The input is malformed.
+    Invalid,
+    /// A value exceeds its maximum length, count or range, or does not fit
+    /// in the output buffer.
+    Overflow,
+    /// A value was rejected by a validator.
+    Validation,
+    /// A tag does not match any known variant.
+    UnknownTag,
+    /// A custom error.
+    Custom,
+}
+
+/// The location at which a derived decoder failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Context {
+    /// The name of the outermost type being decoded.
+    pub ty: &'static str,
+    /// The path of fields leading to the failure, from the outermost type.
+    pub path: Vec<&'static str>,
+    /// The offset of the failing field from the start of the outermost type.
+    pub offset: usize,
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.ty)?;
+
+        for field in &self.path {
+            write!(f, ".{field}")?;
+        }
+
+        write!(f, " at byte offset {}", self.offset)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error(
@@ -16,8 +60,255 @@ pub enum Error {
         available: usize,
     },
 
+    #[error(
+        "buffer too small: {} bytes needed ({} bytes available)",
+        needed,
+        available
+    )]
+    BufferTooSmall { needed: usize, available: usize },
+
+    #[error("{0} bytes left over after decoding a nested value")]
+    TrailingBytes(usize),
+
+    #[error("frame of {len} bytes ends before its value")]
+    TruncatedFrame { len: usize },
+
+    #[error("repeated element decoded from zero bytes")]
+    EmptyElement,
+
+    #[error("no delimiter found within {max} bytes")]
+    DelimiterNotFound { max: usize },
+
+    #[error("value contains its delimiter at byte offset {offset}")]
+    UnexpectedDelimiter { offset: usize },
+
+    #[error("value length {len} exceeds maximum of {max}")]
+    LengthExceeded { len: usize, max: usize },
+
+    #[error("invalid length {len}, expected {expected}")]
+    InvalidLength { len: usize, expected: usize },
+
+    #[error("invalid padding at byte offset {offset}")]
+    InvalidPadding { offset: usize },
+
+    #[error("invalid boolean value {0}")]
+    InvalidBool(u8),
+
+    #[error("invalid unicode code point {0:#x}")]
+    InvalidChar(u32),
+
+    #[error("invalid presence flag {0}")]
+    InvalidFlag(usize),
+
+    #[error("value is reserved as a sentinel")]
+    ReservedValue,
+
+    #[error("invalid magic bytes")]
+    InvalidMagic,
+
+    #[error("invalid constant: expected {expected}, found {found}")]
+    InvalidConstant { expected: String, found: String },
+
+    #[error("reserved byte at offset {offset} is not zero")]
+    NonZeroReserved { offset: usize },
+
+    #[error("field `{field}` cannot be converted from/to `{ty}`")]
+    InvalidConversion {
+        field: &'static str,
+        ty: &'static str,
+    },
+
+    #[error("invalid utf-8 sequence at byte offset {offset}")]
+    InvalidUtf8 { offset: usize },
+
+    #[error("unknown tag: {0}")]
+    UnknownTag(usize),
+
+    #[error("variable-length integer is longer than {max} bytes")]
+    VarIntOverlong { max: usize },
+
+    #[error("variable-length integer overflow")]
+    VarIntOverflow,
+
+    #[error("value does not fit in a {bits}-bit field")]
+    BitFieldOverflow { bits: u32 },
+
+    #[error("element count {count} exceeds maximum of {max}")]
+    CountExceeded { count: usize, max: usize },
+
     #[error("integer conversion")]
     IntConversion(#[from] crate::codec::TryFromIntError),
+
+    #[error("validation failed: {0}")]
+    Validation(String),
+
+    #[error(transparent)]
+    Custom(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("{context}: {source}")]
+    Context {
+        context: Context,
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Creates an error for a value that was rejected by a validator.
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::Validation(message.into())
+    }
+
+    /// Creates an error for a constant that was decoded as `found` instead of
+    /// `expected`.
+    pub fn invalid_constant(
+        expected: &impl fmt::Debug,
+        found: &impl fmt::Debug,
+    ) -> Self {
+        Self::InvalidConstant {
+            expected: format!("{expected:?}"),
+            found: format!("{found:?}"),
+        }
+    }
+
+    /// Creates an error from a custom error.
+    pub fn custom(
+        error: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        Self::Custom(error.into())
+    }
+
+    /// Returns the category of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            | Self::BytesNeeded { .. } => ErrorKind::Incomplete,
+            | Self::TrailingBytes(_)
+            | Self::TruncatedFrame { .. }
+            | Self::EmptyElement
+            | Self::UnexpectedDelimiter { .. }
+            | Self::InvalidLength { .. }
+            | Self::InvalidPadding { .. }
+            | Self::InvalidBool(_)
+            | Self::InvalidChar(_)
+            | Self::InvalidFlag(_)
+            | Self::ReservedValue
+            | Self::InvalidMagic
+            | Self::InvalidConstant { .. }
+            | Self::NonZeroReserved { .. }
+            | Self::InvalidConversion { .. }
+            | Self::InvalidUtf8 { .. }
+            | Self::VarIntOverlong { .. } => ErrorKind::Invalid,
+            | Self::BufferTooSmall { .. }
+            | Self::DelimiterNotFound { .. }
+            | Self::LengthExceeded { .. }
+            | Self::VarIntOverflow
+            | Self::BitFieldOverflow { .. }
+            | Self::CountExceeded { .. }
+            | Self::IntConversion(_) => ErrorKind::Overflow,
+            | Self::Validation(_) => ErrorKind::Validation,
+            | Self::UnknownTag(_) => ErrorKind::UnknownTag,
+            | Self::Custom(_) => ErrorKind::Custom,
+            | Self::Context { source, .. } => source.kind(),
+        }
+    }
+
+    /// Returns the location at which a derived decoder failed, if known.
+    pub fn context(&self) -> Option<&Context> {
+        match self {
+            | Self::Context { context, .. } => Some(context),
+            | _ => None,
+        }
+    }
+
+    /// Returns the underlying error, without its [`Context`].
+    pub fn root(&self) -> &Error {
+        match self {
+            | Self::Context { source, .. } => source,
+            | _ => self,
+        }
+    }
+
+    /// Reports an incomplete value that was decoded from a frame of `len`
+    /// bytes as [`Error::TruncatedFrame`], as more input cannot complete it.
+    pub(crate) fn truncate_frame(&mut self, len: usize) {
+        match self {
+            | Self::Context { source, .. } => source.truncate_frame(len),
+            | Self::BytesNeeded { .. } => *self = Self::TruncatedFrame { len },
+            | _ => {}
+        }
+    }
+
+    /// Records that the error occurred in `field` of `ty`, which starts at
+    /// `offset`.
+    fn push_context(
+        &mut self,
+        ty: &'static str,
+        field: Option<&'static str>,
+        offset: usize,
+    ) {
+        if let Self::Context { context, .. } = self {
+            context.ty = ty;
+            context.path.splice(..0, field);
+            context.offset += offset;
+
+            return;
+        }
+
+        let source = std::mem::replace(self, Self::VarIntOverflow);
+
+        *self = Self::Context {
+            context: Context {
+                ty,
+                path: field.into_iter().collect(),
+                offset,
+            },
+            source: Box::new(source),
+        };
+    }
+}
+
+/// Adds the location of a failure to `error`, if it is (or boxes) an
+/// [`Error`]. Other error types are returned unchanged.
+///
+/// This is used by derived decoders.
+#[doc(hidden)]
+pub fn add_context<E: 'static>(
+    mut error: E,
+    ty: &'static str,
+    field: Option<&'static str>,
+    offset: usize,
+) -> E {
+    if let Some(inner) = downcast_mut(&mut error) {
+        inner.push_context(ty, field, offset);
+    }
+
+    error
+}
+
+/// Reports an incomplete value that was decoded from a frame of `len` bytes
+/// as [`Error::TruncatedFrame`], if `error` is (or boxes) an [`Error`]. Other
+/// error types are returned unchanged.
+pub(crate) fn truncate_frame<E: 'static>(mut error: E, len: usize) -> E {
+    if let Some(inner) = downcast_mut(&mut error) {
+        inner.truncate_frame(len);
+    }
+
+    error
+}
+
+/// Returns the [`Error`] that `error` is (or boxes), if any.
+fn downcast_mut<E: 'static>(error: &mut E) -> Option<&mut Error> {
+    type Boxed = Box<dyn std::error::Error>;
+    type BoxedSend = Box<dyn std::error::Error + Send + Sync>;
+
+    let any = error as &mut dyn Any;
+
+    if any.is::<Boxed>() {
+        any.downcast_mut::<Boxed>()?.downcast_mut()
+    } else if any.is::<BoxedSend>() {
+        any.downcast_mut::<BoxedSend>()?.downcast_mut()
+    } else {
+        any.downcast_mut()
+    }
 }
 
 impl From<std::convert::Infallible> for Error {
@@ -0,0 +1,85 @@
+//! Codecs whose values always take the same number of bytes.
+
+/// A codec whose values are always decoded from, and encoded into, exactly
+/// [`SIZE`](Self::SIZE) bytes.
+///
+/// This is implemented for the fixed-width codecs of this crate (numbers,
+/// `ux` types, `bool`, `char` and
+/// [`Padded`](crate::codec::Padded)), and for arrays and tuples of fixed size
+/// codecs. It can be derived for structs whose fields are all fixed size.
+pub trait FixedSize {
+    /// The number of bytes of a value.
+    const SIZE: usize;
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate as recode;
+    #[cfg(feature = "ux")]
+    use crate::codec::u24;
+    use crate::{codec::Le, util::BufExt, Decoder, Encoder, FixedSize, Recode};
+
+    #[derive(Debug, PartialEq, Eq, Recode, FixedSize)]
+    #[recode(magic = b"HD", endian = "little", error = "crate::Error")]
+    struct Header {
+        #[recode(after(reserved = 1))]
+        kind: u8,
+        #[recode(bits = 4)]
+        version: u8,
+        #[recode(bits = 4)]
+        flags: u8,
+        #[recode(before(constant(ty = "u16", value = 1)))]
+        len: u16,
+        #[recode(with = "[Le<u16>; 2]")]
+        ids: [u16; 2],
+        #[recode(
+            with = "Le<u16>",
+            decoder(with = "Le<u32>"),
+            encoder(with = "Le<u32>")
+        )]
+        sequence: u32,
+        #[recode(skip)]
+        cached: bool,
+    }
+
+    #[test]
+    fn fixed_size_test() {
+        assert_eq!(<u8 as FixedSize>::SIZE, 1);
+        assert_eq!(<Le<u64> as FixedSize>::SIZE, 8);
+        #[cfg(feature = "ux")]
+        assert_eq!(<u24 as FixedSize>::SIZE, 3);
+        assert_eq!(<(u8, [u32; 4]) as FixedSize>::SIZE, 17);
+        assert_eq!(<char as FixedSize>::SIZE, 4);
+
+        assert_eq!(Header::SIZE, 17);
+        assert_eq!(<Header as Decoder>::MIN_SIZE, Header::SIZE);
+        assert_eq!(<Header as Decoder>::MAX_SIZE, Some(Header::SIZE));
+        assert_eq!(<Header as Encoder>::MAX_SIZE, Some(Header::SIZE));
+    }
+
+    #[test]
+    fn decode_array_test() {
+        let mut frame = Bytes::from_static(&[0x00, 0x01, 0x00, 0x02]);
+
+        assert_eq!(
+            frame.decode_array::<Le<u16>, u16, 2>().unwrap(),
+            [0x0100, 0x0200]
+        );
+
+        let mut short = Bytes::from_static(&[0x00, 0x01, 0x00]);
+        let mut long = Bytes::from_static(&[0x00, 0x01, 0x00, 0x02, 0x03]);
+
+        assert!(matches!(
+            short.decode_array::<u16, u16, 2>(),
+            Err(crate::Error::BytesNeeded { needed: 1, .. })
+        ));
+        assert_eq!(short.len(), 3);
+        assert!(matches!(
+            long.decode_array::<u16, u16, 2>(),
+            Err(crate::Error::TrailingBytes(1))
+        ));
+        assert_eq!(long.len(), 5);
+    }
+}
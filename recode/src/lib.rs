@@ -1,15 +1,19 @@
+pub mod bits;
 pub mod codec;
 pub mod decode;
 pub mod encode;
 pub mod error;
+pub mod fixed;
 pub mod util;
 
 /// Re-export of [`bytes`](https://docs.rs/bytes) crate.
 pub use bytes;
 #[doc(inline)]
-pub use decode::{Decoder, RawDecoder};
+pub use decode::{BorrowDecoder, DecodeWith, Decoder, RawDecoder};
 #[doc(inline)]
-pub use encode::Encoder;
-pub use error::{Error, Result};
+pub use encode::{EncodeWith, Encoder};
+pub use error::{Error, ErrorKind, Result};
+#[doc(inline)]
+pub use fixed::FixedSize;
 #[cfg(feature = "derive")]
-pub use recode_derive::{Decoder, Encoder, Recode};
+pub use recode_derive::{Decoder, Encoder, FixedSize, RawDecoder, Recode};
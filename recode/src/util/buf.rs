@@ -1,4 +1,12 @@
-use bytes::Buf;
+use std::io::IoSlice;
+
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::{Decoder, FixedSize, RawDecoder};
+
+/// The longest value [`BufExt::decode_fixed`] decodes; this is the size of
+/// the longest fixed-size integer.
+const FIXED_DECODE_LEN: usize = 16;
 
 /// Extension trait for [`Buf`](bytes::Buf).
 pub trait BufExt: Buf {
@@ -18,6 +26,278 @@ pub trait BufExt: Buf {
 
         Ok(())
     }
+
+    /// Returns the bytes at the start of the buffer, without consuming them.
+    ///
+    /// If the first chunk of the buffer is at least `scratch.len()` bytes
+    /// long, or holds the whole buffer, it is returned as-is. Otherwise, the
+    /// first `scratch.len()` bytes (or all of them, if there are less) are
+    /// copied from the chunks of the buffer into `scratch`.
+    ///
+    /// Returns `None` if the buffer does not expose that many bytes through
+    /// [`Buf::chunks_vectored`] (e.g. it is split into too many chunks, or
+    /// only exposes its first chunk), in which case they can only be read by
+    /// consuming them.
+    fn peek<'a>(&'a self, scratch: &'a mut [u8]) -> Option<&'a [u8]> {
+        let chunk = self.chunk();
+
+        if chunk.len() >= scratch.len() || chunk.len() == self.remaining() {
+            return Some(chunk);
+        }
+
+        let mut slices = [IoSlice::new(&[]); 64];
+        let n = self.chunks_vectored(&mut slices);
+        let want = scratch.len().min(self.remaining());
+        let mut len = 0;
+
+        for slice in &slices[..n] {
+            let n = slice.len().min(want - len);
+
+            scratch[len..len + n].copy_from_slice(&slice[..n]);
+            len += n;
+
+            if len == want {
+                return Some(&scratch[..len]);
+            }
+        }
+
+        None
+    }
+
+    /// Decodes a value of exactly `len` (at most 16) bytes with the raw
+    /// decoder `D`, and advances the buffer past it.
+    ///
+    /// The buffer is not advanced if it has less than `len` bytes. If the
+    /// value spans several chunks, it is copied out of the buffer first, so
+    /// its bytes are consumed even if `D` fails to decode them.
+    fn decode_fixed<D, T>(&mut self, len: usize) -> Result<T, D::Error>
+    where
+        D: RawDecoder<T>,
+        D::Error: From<crate::Error>,
+    {
+        self.require_n(len)?;
+
+        if let Some(bytes) = self.chunk().get(..len) {
+            let (value, rx) = D::raw_decode(bytes)?;
+
+            self.advance(rx);
+
+            return Ok(value);
+        }
+
+        let mut scratch = [0; FIXED_DECODE_LEN];
+        let bytes = &mut scratch[..len];
+
+        self.copy_to_slice(bytes);
+
+        D::raw_decode(bytes).map(|(value, _)| value)
+    }
+
+    /// Decodes a value from exactly the next `len` bytes of the buffer.
+    ///
+    /// The bytes are split off the buffer and passed to `decode`. Fails with
+    /// [`crate::Error::TrailingBytes`] if `decode` does not consume all of
+    /// them, and with [`crate::Error::TruncatedFrame`] if it needs more. The
+    /// buffer is not advanced if it has less than `len` bytes.
+    fn decode_exact<T, E, F>(&mut self, len: usize, decode: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Bytes) -> Result<T, E>,
+        E: From<crate::Error> + 'static,
+    {
+        self.require_n(len)?;
+
+        let mut frame = self.copy_to_bytes(len);
+        let value = decode(&mut frame)
+            .map_err(|e| crate::error::truncate_frame(e, len))?;
+
+        if frame.has_remaining() {
+            return Err(crate::Error::TrailingBytes(frame.remaining()).into());
+        }
+
+        Ok(value)
+    }
+
+    /// Decodes `N` values with the fixed size codec `C`, which must make up
+    /// the rest of the buffer (e.g. a frame).
+    ///
+    /// The length of the buffer is checked before anything is decoded: fails
+    /// with [`crate::Error::BytesNeeded`] if it is shorter than `N * C::SIZE`
+    /// bytes, and with [`crate::Error::TrailingBytes`] if it is longer.
+    fn decode_array<C, T, const N: usize>(&mut self) -> Result<[T; N], C::Error>
+    where
+        Self: Sized,
+        C: Decoder<T> + FixedSize,
+        C::Error: From<crate::Error>,
+    {
+        let len = C::SIZE * N;
+
+        self.require_n(len)?;
+
+        if self.remaining() > len {
+            return Err(
+                crate::Error::TrailingBytes(self.remaining() - len).into()
+            );
+        }
+
+        <[C; N] as Decoder<[T; N]>>::decode(self)
+    }
+
+    /// Consumes `magic` from the start of the buffer.
+    ///
+    /// Fails with [`crate::Error::InvalidMagic`] if the buffer starts with
+    /// other bytes. The buffer is not advanced if it is shorter than `magic`.
+    fn expect_magic(&mut self, magic: &[u8]) -> crate::Result<()> {
+        self.require_n(magic.len())?;
+
+        for &expected in magic {
+            if self.get_u8() != expected {
+                return Err(crate::Error::InvalidMagic);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Consumes `n` reserved bytes, which must all be zero.
+    ///
+    /// Fails with [`crate::Error::NonZeroReserved`] otherwise. The buffer is
+    /// not advanced if it has less than `n` bytes.
+    fn skip_reserved(&mut self, n: usize) -> crate::Result<()> {
+        self.require_n(n)?;
+
+        for offset in 0..n {
+            if self.get_u8() != 0 {
+                return Err(crate::Error::NonZeroReserved { offset });
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl<T> BufExt for T where T: Buf {}
+
+/// Extension trait for [`BufMut`](bytes::BufMut).
+pub trait BufMutExt: BufMut {
+    /// Checks if the buffer has room for at least `n` more bytes.
+    ///
+    /// If there is less room, returns an [`crate::Error::BufferTooSmall`]
+    /// error with requirement information.
+    #[inline]
+    fn require_capacity(&self, n: usize) -> crate::Result<()> {
+        if self.remaining_mut() < n {
+            return Err(crate::Error::BufferTooSmall {
+                needed: n,
+                available: self.remaining_mut(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> BufMutExt for T where T: BufMut {}
+
+/// A buffer that holds one byte per chunk, for testing decoding from
+/// non-contiguous buffers.
+///
+/// If `vectored` is set, every chunk is exposed through
+/// [`Buf::chunks_vectored`] (like a [`Chain`](bytes::buf::Chain) of one-byte
+/// buffers); otherwise, only the first one is (as by its default
+/// implementation).
+#[cfg(test)]
+pub(crate) struct Segmented<'a> {
+    pub(crate) bytes: &'a [u8],
+    pub(crate) vectored: bool,
+}
+
+#[cfg(test)]
+impl Buf for Segmented<'_> {
+    fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn chunk(&self) -> &[u8] {
+        &self.bytes[..self.bytes.len().min(1)]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.bytes = &self.bytes[cnt..];
+    }
+
+    fn chunks_vectored<'a>(&'a self, dst: &mut [IoSlice<'a>]) -> usize {
+        if !self.vectored {
+            return match (self.bytes.is_empty(), dst.first_mut()) {
+                | (false, Some(slice)) => {
+                    *slice = IoSlice::new(self.chunk());
+                    1
+                }
+                | _ => 0,
+            };
+        }
+
+        let chunks = self.bytes.chunks(1);
+        let n = chunks.len().min(dst.len());
+
+        for (slice, chunk) in dst.iter_mut().zip(chunks) {
+            *slice = IoSlice::new(chunk);
+        }
+
+        n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        codec::{Uleb128, Utf8Char},
+        Decoder,
+        Error,
+    };
+
+    #[test]
+    fn peek_test() {
+        let buf = Segmented {
+            bytes: b"abcd",
+            vectored: false,
+        };
+
+        assert_eq!(buf.peek(&mut [0; 1]), Some(&b"a"[..]));
+        assert_eq!(buf.peek(&mut [0; 4]), None);
+
+        let buf = Segmented {
+            bytes: b"abcd",
+            vectored: true,
+        };
+
+        assert_eq!(buf.peek(&mut [0; 3]), Some(&b"abc"[..]));
+        assert_eq!(buf.peek(&mut [0; 8]), Some(&b"abcd"[..]));
+    }
+
+    #[test]
+    fn segmented_decode_test() {
+        for vectored in [false, true] {
+            let bytes =
+                [0x01, 0x02, 0x03, 0x04, 0xE2, 0x82, 0xAC, 0x80, 0x01, 0x00];
+            let mut buf = Segmented {
+                bytes: &bytes,
+                vectored,
+            };
+
+            assert_eq!(
+                <u32 as Decoder<u32>>::decode(&mut buf).unwrap(),
+                0x01020304
+            );
+            assert_eq!(Utf8Char::decode(&mut buf).unwrap(), '€');
+            assert_eq!(
+                <Uleb128 as Decoder<u64>>::decode(&mut buf).unwrap(),
+                0x80
+            );
+            assert!(matches!(
+                <u16 as Decoder<u16>>::decode(&mut buf),
+                Err(Error::BytesNeeded { needed: 1, .. })
+            ));
+        }
+    }
+}
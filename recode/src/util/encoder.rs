@@ -1,4 +1,4 @@
-use bytes::BytesMut;
+use bytes::BufMut;
 
 use crate::Encoder;
 
@@ -6,10 +6,11 @@ use crate::Encoder;
 pub trait EncoderExt: Encoder + Sized {
     /// Encodes `self` into `buf`.
     ///
-    /// This is just an alias to [`Encoder::encode`], with `self` passed in
-    /// the place of `item`.
+    /// This checks that `buf` has room for `self` with
+    /// [`Encoder::check_capacity`], then encodes it with [`Encoder::encode`].
     #[inline(always)]
-    fn encode_to(&self, buf: &mut BytesMut) -> Result<(), Self::Error> {
+    fn encode_to<B: BufMut>(&self, buf: &mut B) -> Result<(), Self::Error> {
+        <Self as Encoder>::check_capacity(self, buf)?;
         <Self as Encoder>::encode(self, buf)
     }
 
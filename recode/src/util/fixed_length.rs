@@ -0,0 +1,56 @@
+use bytes::{Buf, BufMut};
+
+/// A length type for buffers with a fixed length of `N` bytes.
+///
+/// This type is useful for encoding/decoding buffers that have no length
+/// prefix, and should either:
+/// - decode as exactly `N` bytes.
+/// - encode with no length prefix, failing if the buffer is not `N` bytes long
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixedLength<const N: usize>;
+
+impl<const N: usize> crate::Decoder<usize> for FixedLength<N> {
+    type Error = crate::Error;
+
+    #[inline]
+    fn decode<B: Buf>(_buf: &mut B) -> Result<usize, Self::Error> {
+        Ok(N)
+    }
+}
+
+impl<const N: usize> crate::RawDecoder<usize> for FixedLength<N> {
+    type Error = crate::Error;
+
+    #[inline]
+    fn raw_decode<'a>(_buf: &'a [u8]) -> Result<(usize, usize), Self::Error>
+    where
+        usize: 'a,
+    {
+        Ok((N, 0))
+    }
+}
+
+impl<const N: usize> crate::Encoder<usize> for FixedLength<N> {
+    type Error = crate::Error;
+
+    #[inline]
+    fn encode<B: BufMut>(
+        input: &usize,
+        _buf: &mut B,
+    ) -> Result<(), Self::Error> {
+        match *input {
+            | len if len > N => {
+                Err(crate::Error::LengthExceeded { len, max: N })
+            }
+            | len if len < N => {
+                Err(crate::Error::InvalidLength { len, expected: N })
+            }
+            | _ => Ok(()),
+        }
+    }
+
+    #[inline]
+    fn size_of(_input: &usize) -> usize {
+        0
+    }
+}
@@ -1,10 +1,19 @@
 mod buf;
 mod encoder;
+mod fixed_length;
 mod remaining;
+pub mod size;
+mod utf8_bytes;
 
+#[cfg(test)]
+pub(crate) use buf::Segmented;
 #[doc(inline)]
-pub use buf::BufExt;
+pub use buf::{BufExt, BufMutExt};
 #[doc(inline)]
 pub use encoder::EncoderExt;
 #[doc(inline)]
+pub use fixed_length::FixedLength;
+#[doc(inline)]
 pub use remaining::Remaining;
+#[doc(inline)]
+pub use utf8_bytes::Utf8Bytes;
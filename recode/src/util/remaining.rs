@@ -1,4 +1,4 @@
-use bytes::BytesMut;
+use bytes::{Buf, BufMut};
 
 /// A to represent a length type with zero length.
 ///
@@ -13,8 +13,8 @@ impl crate::Decoder<usize> for Remaining {
     type Error = crate::Error;
 
     #[inline]
-    fn decode(buf: &mut BytesMut) -> Result<usize, Self::Error> {
-        Ok(buf.len())
+    fn decode<B: Buf>(buf: &mut B) -> Result<usize, Self::Error> {
+        Ok(buf.remaining())
     }
 }
 
@@ -34,7 +34,10 @@ impl crate::Encoder<usize> for Remaining {
     type Error = crate::Error;
 
     #[inline]
-    fn encode(_input: &usize, _buf: &mut BytesMut) -> Result<(), Self::Error> {
+    fn encode<B: BufMut>(
+        _input: &usize,
+        _buf: &mut B,
+    ) -> Result<(), Self::Error> {
         Ok(())
     }
 
@@ -0,0 +1,90 @@
+//! `const` helpers to combine the [`MIN_SIZE`](crate::Decoder::MIN_SIZE) and
+//! [`MAX_SIZE`](crate::Decoder::MAX_SIZE) of several codecs.
+
+/// Returns the maximum size of consecutive values with the given maximum
+/// sizes, or `None` if any of them is unbounded.
+pub const fn sum_max(sizes: &[Option<usize>]) -> Option<usize> {
+    let mut total: usize = 0;
+    let mut i = 0;
+
+    while i < sizes.len() {
+        let Some(size) = sizes[i] else {
+            return None;
+        };
+        let Some(sum) = total.checked_add(size) else {
+            return None;
+        };
+
+        total = sum;
+        i += 1;
+    }
+
+    Some(total)
+}
+
+/// Returns the maximum size of a value that is one of several alternatives
+/// with the given maximum sizes, or `None` if any of them is unbounded.
+pub const fn max_of(sizes: &[Option<usize>]) -> Option<usize> {
+    let mut max = 0;
+    let mut i = 0;
+
+    while i < sizes.len() {
+        match sizes[i] {
+            | Some(size) if size > max => max = size,
+            | Some(_) => {}
+            | None => return None,
+        }
+
+        i += 1;
+    }
+
+    Some(max)
+}
+
+/// Returns the minimum size of a value that is one of several alternatives
+/// with the given minimum sizes, or `0` if there are none.
+pub const fn min_of(sizes: &[usize]) -> usize {
+    if sizes.is_empty() {
+        return 0;
+    }
+
+    let mut min = sizes[0];
+    let mut i = 1;
+
+    while i < sizes.len() {
+        if sizes[i] < min {
+            min = sizes[i];
+        }
+
+        i += 1;
+    }
+
+    min
+}
+
+/// Returns the maximum size of `n` consecutive values with the given maximum
+/// size, or `None` if it is unbounded.
+pub const fn mul_max(size: Option<usize>, n: usize) -> Option<usize> {
+    match size {
+        | Some(size) => size.checked_mul(n),
+        | None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combine_test() {
+        assert_eq!(sum_max(&[Some(1), Some(2)]), Some(3));
+        assert_eq!(sum_max(&[Some(1), None]), None);
+        assert_eq!(sum_max(&[Some(usize::MAX), Some(1)]), None);
+        assert_eq!(max_of(&[Some(1), Some(4), Some(2)]), Some(4));
+        assert_eq!(max_of(&[Some(1), None]), None);
+        assert_eq!(min_of(&[3, 1, 2]), 1);
+        assert_eq!(min_of(&[]), 0);
+        assert_eq!(mul_max(Some(4), 3), Some(12));
+        assert_eq!(mul_max(None, 3), None);
+    }
+}
@@ -0,0 +1,101 @@
+use std::{fmt, ops::Deref};
+
+use bytes::Bytes;
+
+/// A cheaply cloneable [`Bytes`] buffer that is guaranteed to hold valid
+/// UTF-8.
+///
+/// This can be decoded in place of [`String`] to avoid copying large text
+/// payloads out of the source buffer.
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Utf8Bytes(Bytes);
+
+impl Utf8Bytes {
+    /// Creates a new [`Utf8Bytes`] from a static string slice.
+    #[inline]
+    pub const fn from_static(value: &'static str) -> Self {
+        Self(Bytes::from_static(value.as_bytes()))
+    }
+
+    /// Returns the contents as a string slice.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        // SAFETY: the contents are validated to be UTF-8 on construction.
+        unsafe { std::str::from_utf8_unchecked(&self.0) }
+    }
+
+    /// Returns the underlying [`Bytes`] buffer.
+    #[inline]
+    pub fn into_bytes(self) -> Bytes {
+        self.0
+    }
+}
+
+impl TryFrom<Bytes> for Utf8Bytes {
+    type Error = crate::Error;
+
+    #[inline]
+    fn try_from(value: Bytes) -> Result<Self, Self::Error> {
+        std::str::from_utf8(&value).map_err(|e| crate::Error::InvalidUtf8 {
+            offset: e.valid_up_to(),
+        })?;
+
+        Ok(Self(value))
+    }
+}
+
+impl From<String> for Utf8Bytes {
+    #[inline]
+    fn from(value: String) -> Self {
+        Self(value.into())
+    }
+}
+
+impl From<&'static str> for Utf8Bytes {
+    #[inline]
+    fn from(value: &'static str) -> Self {
+        Self::from_static(value)
+    }
+}
+
+impl From<Utf8Bytes> for Bytes {
+    #[inline]
+    fn from(value: Utf8Bytes) -> Self {
+        value.0
+    }
+}
+
+impl Deref for Utf8Bytes {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Utf8Bytes {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<[u8]> for Utf8Bytes {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Utf8Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Utf8Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}